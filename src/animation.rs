@@ -0,0 +1,261 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use crate::game::{GameEvent, GameState};
+
+// Short animations drawn over the board: the selection sliding to the next
+// cell, placed digits popping in, conflicts shaking and flashing, and a sweep
+// over every row, column or region the player fills, and a wave over the
+// whole board when the solver fills it in. They are started from
+// the events of the GameState API and advanced by the frame time, so nothing
+// in the game rules waits for them.
+
+const CURSOR_MOVE: Duration = Duration::from_millis(90);
+const POP_IN: Duration = Duration::from_millis(180);
+const SHAKE: Duration = Duration::from_millis(360);
+const SWEEP: Duration = Duration::from_millis(500);
+const SOLVE_WAVE: Duration = Duration::from_millis(900);
+
+// How a tween gets from its start to its end value, t runs from 0 to 1
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Easing {
+    Linear,
+    // Fast at first, then settling
+    EaseOut,
+    // Runs a little past the end and comes back
+    Overshoot,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::Overshoot => {
+                let s = 1.7;
+                let t = t - 1.0;
+                t * t * ((s + 1.0) * t + s) + 1.0
+            }
+        }
+    }
+}
+
+// A value moving from one number to another over a fixed time
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Tween {
+    pub from: f32,
+    pub to: f32,
+    pub duration: Duration,
+    pub elapsed: Duration,
+    pub easing: Easing,
+}
+
+impl Tween {
+    pub fn new(from: f32, to: f32, duration: Duration, easing: Easing) -> Tween {
+        Tween { from, to, duration, elapsed: Duration::ZERO, easing }
+    }
+
+    pub fn advance(&mut self, dt: Duration) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    // How far along the tween is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.from + (self.to - self.from) * self.easing.apply(self.progress())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+// Brightness of a cell under a wave of light running along a line of cells,
+// from 0 to 1. position is where the cell lies along the line and width the
+// share of the animation each cell stays lit.
+pub fn wave(progress: f32, position: f32, width: f32) -> f32 {
+    let t = (progress - position * (1.0 - width)) / width;
+    if (0.0..1.0).contains(&t) {
+        1.0 - (2.0 * t - 1.0).abs()
+    } else {
+        0.0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Effect {
+    PopIn,
+    // The conflicting digit shakes while the cell flashes in the conflict color
+    Shake,
+    // Part of a filled row, column or region, position is how far along it
+    Sweep { position: f32 },
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct CellAnimation {
+    row: usize,
+    col: usize,
+    effect: Effect,
+    tween: Tween,
+}
+
+// The cursor on its way from one cell to the next
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct CursorMove {
+    target: (i32, i32),
+    row: Tween,
+    col: Tween,
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Animations {
+    cursor: Option<CursorMove>,
+    cells: Vec<CellAnimation>,
+}
+
+impl Animations {
+    // Start the animations for an event. Does nothing while motion is turned
+    // off in the settings.
+    pub fn start(&mut self, event: &GameEvent, game_state: &GameState) {
+        if !game_state.config.animations {
+            return;
+        }
+        match *event {
+            GameEvent::DigitPlaced { row, col, .. } | GameEvent::HintPlaced { row, col } => {
+                self.add(row, col, Effect::PopIn, Tween::new(0.4, 1.0, POP_IN, Easing::Overshoot));
+                self.sweep_filled_houses(row, col, game_state);
+            }
+            GameEvent::Conflict { row, col, .. } => {
+                self.add(row, col, Effect::Shake, Tween::new(1.0, 0.0, SHAKE, Easing::Linear));
+            }
+            GameEvent::Solved => self.wave_over_board(game_state),
+            _ => {}
+        }
+    }
+
+    // Every row, column or region the cell belongs to that is now full and
+    // free of conflicts lights up from one end to the other
+    fn sweep_filled_houses(&mut self, row: usize, col: usize, game_state: &GameState) {
+        let conflicted = |&(r, c): &(usize, usize)| {
+            game_state.invalid_positions.iter().any(|&(xi, yi, _)| (xi as usize, yi as usize) == (r, c))
+        };
+        for house in game_state.regions.houses() {
+            if !house.contains(&(row, col)) ||
+                house.iter().any(|&(r, c)| game_state.board[r][c].is_none()) ||
+                house.iter().any(conflicted)
+            {
+                continue;
+            }
+            let last = (house.len() - 1).max(1) as f32;
+            for (index, &(r, c)) in house.iter().enumerate() {
+                let position = index as f32 / last;
+                self.add(r, c, Effect::Sweep { position }, Tween::new(0.0, 1.0, SWEEP, Easing::Linear));
+            }
+        }
+    }
+
+    // A sweep running diagonally from the top-left corner to the bottom-right one
+    fn wave_over_board(&mut self, game_state: &GameState) {
+        let size = game_state.regions.size();
+        let last = (2 * size - 2).max(1) as f32;
+        for row in 0..size {
+            for col in (0..size).filter(|&col| game_state.regions.is_active(row, col)) {
+                let position = (row + col) as f32 / last;
+                self.add(row, col, Effect::Sweep { position }, Tween::new(0.0, 1.0, SOLVE_WAVE, Easing::Linear));
+            }
+        }
+    }
+
+    fn add(&mut self, row: usize, col: usize, effect: Effect, tween: Tween) {
+        // A cell pops in or shakes once at a time, sweeps along a row and a
+        // column may cross it together
+        if !matches!(effect, Effect::Sweep { .. }) {
+            self.cells.retain(|cell| (cell.row, cell.col) != (row, col) || cell.effect != effect);
+        }
+        self.cells.push(CellAnimation { row, col, effect, tween });
+    }
+
+    // Slide towards the cursor whenever it moved to another cell. Only a
+    // lone selected cell slides, a larger selection jumps.
+    pub fn follow_cursor(&mut self, game_state: &GameState) {
+        let target = game_state.selected_square.filter(|_| game_state.selected_cells.len() == 1);
+        let Some(target) = target else {
+            self.cursor = None;
+            return;
+        };
+        // A cursor that just appeared is put in place right away
+        let (from_row, from_col, duration) = match self.cursor {
+            Some(cursor) if cursor.target == target => return,
+            Some(cursor) if game_state.config.animations => (cursor.row.value(), cursor.col.value(), CURSOR_MOVE),
+            _ => (target.0 as f32, target.1 as f32, Duration::ZERO),
+        };
+        self.cursor = Some(CursorMove {
+            target,
+            row: Tween::new(from_row, target.0 as f32, duration, Easing::EaseOut),
+            col: Tween::new(from_col, target.1 as f32, duration, Easing::EaseOut),
+        });
+    }
+
+    pub fn advance(&mut self, dt: Duration) {
+        if let Some(cursor) = &mut self.cursor {
+            cursor.row.advance(dt);
+            cursor.col.advance(dt);
+        }
+        for cell in &mut self.cells {
+            cell.tween.advance(dt);
+        }
+        self.cells.retain(|cell| !cell.tween.is_done());
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.cells.is_empty() || self.cursor.is_some_and(|cursor| !cursor.row.is_done() || !cursor.col.is_done())
+    }
+
+    // Where the cursor is drawn in rows and columns while it slides, None
+    // once it rests on its cell
+    pub fn cursor(&self) -> Option<(f32, f32)> {
+        self.cursor
+            .filter(|cursor| !cursor.row.is_done() || !cursor.col.is_done())
+            .map(|cursor| (cursor.row.value(), cursor.col.value()))
+    }
+
+    fn find(&self, row: usize, col: usize, matches: fn(&Effect) -> bool) -> Option<&CellAnimation> {
+        self.cells.iter().find(|cell| (cell.row, cell.col) == (row, col) && matches(&cell.effect))
+    }
+
+    // Size of the digit in a cell relative to its full size
+    pub fn scale(&self, row: usize, col: usize) -> f32 {
+        self.find(row, col, |effect| *effect == Effect::PopIn).map_or(1.0, |cell| cell.tween.value())
+    }
+
+    // Sideways offset of a shaking digit, as a share of the largest offset
+    pub fn shake(&self, row: usize, col: usize) -> f32 {
+        self.find(row, col, |effect| *effect == Effect::Shake)
+            .map_or(0.0, |cell| (cell.tween.progress() * 6.0 * PI).sin() * cell.tween.value())
+    }
+
+    // Strength of the conflict flash over a cell, from 0 to 1
+    pub fn flash(&self, row: usize, col: usize) -> f32 {
+        self.find(row, col, |effect| *effect == Effect::Shake).map_or(0.0, |cell| cell.tween.value())
+    }
+
+    // Brightness of the sweep over a cell, from 0 to 1
+    pub fn sweep(&self, row: usize, col: usize) -> f32 {
+        self.cells
+            .iter()
+            .filter(|cell| (cell.row, cell.col) == (row, col))
+            .filter_map(|cell| match cell.effect {
+                Effect::Sweep { position } => Some(wave(cell.tween.progress(), position, 0.4)),
+                _ => None,
+            })
+            .fold(0.0, f32::max)
+    }
+}
@@ -0,0 +1,3 @@
+fn main() -> Result<(), String> {
+    sudoku::tui::run()
+}
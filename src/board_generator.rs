@@ -1,6 +1,16 @@
-use rand::{seq::SliceRandom, Rng};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::constraints::Constraints;
+use crate::regions::Regions;
+use crate::solver::Solver;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BoardDifficulty {
     Beginner,
     Easy,
@@ -9,47 +19,139 @@ pub enum BoardDifficulty {
     Expert,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardLayout {
+    Classic,
+    Jigsaw,
+    Samurai,
+}
+
 pub struct BoardGenerator {
     difficulty: BoardDifficulty,
+    layout: BoardLayout,
+    solver: Solver,
     solved_board: Option<Vec<Vec<Option<i32>>>>,
 }
 
 impl BoardGenerator {
     pub fn new(difficulty: BoardDifficulty) -> BoardGenerator {
-        BoardGenerator { difficulty, solved_board: None }
+        Self::new_with_layout(difficulty, BoardLayout::Classic)
+    }
+
+    pub fn new_with_layout(difficulty: BoardDifficulty, layout: BoardLayout) -> BoardGenerator {
+        Self::new_with_variant(difficulty, layout, Constraints::default())
+    }
+
+    pub fn new_with_variant(
+        difficulty: BoardDifficulty,
+        layout: BoardLayout,
+        constraints: Constraints
+    ) -> BoardGenerator {
+        BoardGenerator {
+            difficulty,
+            layout,
+            solver: Solver::new_with_constraints(Regions::classic(), constraints),
+            solved_board: None,
+        }
     }
 
     pub fn get_solved_board(&self) -> Option<Vec<Vec<Option<i32>>>> {
         self.solved_board.clone()
     }
 
+    pub fn get_regions(&self) -> Regions {
+        self.solver.regions().clone()
+    }
+
+    pub fn get_constraints(&self) -> Constraints {
+        self.solver.constraints()
+    }
+
     pub fn generate_sudoku(&mut self) -> Vec<Vec<Option<i32>>> {
-        let mut rng = rand::thread_rng();
-        
+        self.generate_with(&mut rand::thread_rng(), &AtomicBool::new(false)).unwrap()
+    }
+
+    // The same seed always gives the same puzzle, on any machine
+    pub fn generate_sudoku_seeded(&mut self, seed: u64) -> Vec<Vec<Option<i32>>> {
+        self.generate_with(&mut ChaCha8Rng::seed_from_u64(seed), &AtomicBool::new(false)).unwrap()
+    }
+
+    // Like generate_sudoku, but gives up with None as soon as another thread
+    // sets cancel
+    pub fn generate_sudoku_cancellable(&mut self, cancel: &AtomicBool) -> Option<Vec<Vec<Option<i32>>>> {
+        self.generate_with(&mut rand::thread_rng(), cancel)
+    }
+
+    // Like generate_sudoku_seeded, but gives up with None as soon as another
+    // thread sets cancel
+    pub fn generate_sudoku_seeded_cancellable(&mut self, seed: u64, cancel: &AtomicBool) -> Option<Vec<Vec<Option<i32>>>> {
+        self.generate_with(&mut ChaCha8Rng::seed_from_u64(seed), cancel)
+    }
+
+    fn generate_with<R: Rng>(&mut self, rng: &mut R, cancel: &AtomicBool) -> Option<Vec<Vec<Option<i32>>>> {
         loop {
-            let mut board_raw: Vec<Vec<Option<i32>>> = vec![vec![None; 9]; 9];
-            if self.fill_board(&mut board_raw, 0) {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            let regions = match self.layout {
+                BoardLayout::Samurai => Regions::samurai(),
+                _ => Regions::classic(),
+            };
+            let size = regions.size();
+            let filler = Solver::new_with_constraints(regions, self.solver.constraints());
+
+            let mut board_raw: Vec<Vec<Option<i32>>> = vec![vec![None; size]; size];
+            if filler.fill_random(&mut board_raw, rng, 20_000) {
+                // Jigsaw regions are shaped around the solution so it stays valid
+                if self.layout == BoardLayout::Jigsaw {
+                    self.solver = Solver::new_with_constraints(
+                        Regions::random_jigsaw(&board_raw, rng),
+                        self.solver.constraints()
+                    );
+                } else {
+                    self.solver = filler;
+                }
                 self.solved_board = Some(board_raw.clone());
     
+                // Cells to remove from a classic board, leaving 51, 36, 28
+                // and mostly 25 givens. Below about 25 givens nearly every
+                // further removal breaks uniqueness, so Expert removes all it
+                // can, which ends between 22 and 27 givens.
                 let removals = match self.difficulty {
-                    BoardDifficulty::Beginner => 30,
-                    BoardDifficulty::Easy => 45,
-                    BoardDifficulty::Medium => 55,
-                    BoardDifficulty::Hard => 62,
-                    BoardDifficulty::Expert => 64,
+                    BoardDifficulty::Beginner => Some(30),
+                    BoardDifficulty::Easy => Some(45),
+                    BoardDifficulty::Medium => Some(53),
+                    BoardDifficulty::Hard => Some(56),
+                    BoardDifficulty::Expert => None,
                 };
     
-                for _ in 0..removals {
-                    loop {
-                        let row = rng.gen_range(0..9);
-                        let col = rng.gen_range(0..9);
-                        if !board_raw[row][col].is_none() {
-                            board_raw[row][col] = None;
-                            break;
-                        }
+                // Remove cells in random order, skipping any removal that would
+                // leave the puzzle with more than one solution (or takes too long
+                // to prove unique). Larger boards get proportionally more removals.
+                let mut cells: Vec<(usize, usize)> = (0..size * size)
+                    .map(|i| (i / size, i % size))
+                    .filter(|&(row, col)| self.solver.regions().is_active(row, col))
+                    .collect();
+                let removals = removals.map_or(cells.len(), |removals| removals * cells.len() / 81);
+                cells.shuffle(rng);
+
+                let mut removed = 0;
+                for (row, col) in cells {
+                    if removed == removals {
+                        break;
+                    }
+                    if cancel.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let value = board_raw[row][col].take();
+                    if self.solver.count_solutions_within(&board_raw, 2, 10_000) == Some(1) {
+                        removed += 1;
+                    } else {
+                        board_raw[row][col] = value;
                     }
                 }
-                return board_raw;
+                return Some(board_raw);
             }
         }
     }
@@ -66,61 +168,31 @@ impl BoardGenerator {
             .join("\n")
     }
 
-    fn fill_board(&self, board: &mut Vec<Vec<Option<i32>>>, position: usize) -> bool {
-        if position == 81 {
-            return true;
-        }
-    
-        let row = position / 9;
-        let col = position % 9;
-    
-        if !board[row][col].is_none() {
-            return self.fill_board(board, position + 1);
-        }
-    
-        let mut rng = rand::thread_rng();
-        let mut numbers: Vec<i32> = (1..=9).collect();
-        numbers.shuffle(&mut rng);
-    
-        for num in numbers {
-            if self.is_valid(board, row, col, num) {
-                board[row][col] = Some(num);
-                if self.fill_board(board, position + 1) {
-                    return true;
-                }
-                board[row][col] = None;
-            }
-        }
-    
-        false
-    }
-
-    pub fn get_square(&self, board: &Vec<Vec<Option<i32>>>, row: usize, col: usize) -> Vec<Vec<Option<i32>>> {
-        let start_row = row - (row % 3);
-        let start_col = col - (col % 3);
-        let mut result = Vec::new();
+    // The digits of the region the cell belongs to, a list per board row the
+    // region spans. A 3x3 box for classic boards, any shape for jigsaw ones,
+    // and nothing for a cell outside the grids.
+    pub fn get_square(&self, board: &[Vec<Option<i32>>], row: usize, col: usize) -> Vec<Vec<Option<i32>>> {
+        let regions = self.solver.regions();
+        let Some(region) = regions.region_of(row, col) else {
+            return Vec::new();
+        };
 
-        for i in start_row..(start_row + 3) {
-            let mut row_slice = Vec::new();
-            for j in start_col..(start_col + 3) {
-                row_slice.push(board[i][j]);
+        let mut result: Vec<Vec<Option<i32>>> = Vec::new();
+        let mut current_row = None;
+        for (i, j) in regions.cells(region) {
+            if current_row != Some(i) {
+                result.push(Vec::new());
+                current_row = Some(i);
             }
-            result.push(row_slice);
+            result.last_mut().unwrap().push(board[i][j]);
         }
 
         result
     }
 
     pub fn is_valid(&self, board: &Vec<Vec<Option<i32>>>, row: usize, col: usize, num: i32) -> bool {
-        !(// check if the number already exists in the row
-          (0..9).any(|i| board[row][i].unwrap_or_default() == num)
-          // check if the number already exists in the column
-          || (0..9).any(|i| board[i][col].unwrap_or_default() == num)
-          // check if the number already exists in the 3x3 square
-          || self.get_square(board, row, col)
-                .iter()
-                .flatten()
-                .any(|&x| x == Some(num)))
+        // check the row, the column and the region the cell belongs to
+        self.solver.is_valid(board, row, col, num)
     }
     
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::board_generator::BoardDifficulty;
+
+// Settings read from config.toml in the user's config directory
+// (~/.config/sudoku on Linux). Every field has a default, so the file may
+// leave out anything it doesn't want to change, or not exist at all.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub window: WindowConfig,
+    pub fonts: FontConfig,
+    pub difficulty: BoardDifficulty,
+    // Name of a built-in or custom theme
+    pub theme: String,
+    // Action name to key chords, replacing the default keys of that action,
+    // see input::InputAction for the names
+    pub keys: BTreeMap<String, Vec<String>>,
+    // Turn off to have every change show up at once, without motion
+    pub animations: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct WindowConfig {
+    // Initial window height as a percentage of the usable display height
+    pub height_percent: u32,
+    // Upper limit for the initial width as a percentage of the display width
+    pub max_width_percent: u32,
+}
+
+// Paths to TrueType fonts replacing the built-in ones
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct FontConfig {
+    // Used for buttons, pencil marks and player entries
+    pub regular: Option<String>,
+    // Used for the given digits and messages
+    pub bold: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            window: WindowConfig::default(),
+            fonts: FontConfig::default(),
+            difficulty: BoardDifficulty::Medium,
+            theme: "Light".to_string(),
+            keys: BTreeMap::new(),
+            animations: true,
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            height_percent: 90,
+            max_width_percent: 90,
+        }
+    }
+}
+
+impl Config {
+    // Where the config file lives, None if the platform has no home directory
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "sudoku").map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    // Read the config file, falling back to the defaults if it is missing.
+    // A broken file is reported and ignored so the game still starts.
+    pub fn load() -> Config {
+        let Some(path) = Self::path() else {
+            return Config::default();
+        };
+        if !path.exists() {
+            return Config::default();
+        }
+        Self::from_file(&path).unwrap_or_else(|e| {
+            eprintln!("Ignoring config file: {}", e);
+            Config::default()
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no config directory on this platform")?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// Relative asset paths are looked up in the working directory first and then
+// next to the executable, so the game also starts when launched from elsewhere
+pub fn resolve_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() || path.exists() {
+        return path;
+    }
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(&path)))
+        .filter(|candidate| candidate.exists())
+        .unwrap_or(path)
+}
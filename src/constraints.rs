@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+// Optional global constraints on top of the regular row, column and region
+// rules, as used by "miracle"-style variants.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Constraints {
+    // Cells a chess knight's move apart may not contain the same digit
+    pub anti_knight: bool,
+    // Diagonally touching cells may not contain the same digit
+    pub anti_king: bool,
+    // Orthogonally adjacent cells may not contain consecutive digits
+    pub non_consecutive: bool,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        !(self.anti_knight || self.anti_king || self.non_consecutive)
+    }
+
+    // Cells of a size x size board that may not share a digit with (row, col)
+    // because of these constraints
+    pub fn peers(&self, row: usize, col: usize, size: usize) -> Vec<(usize, usize)> {
+        let mut moves: Vec<(i32, i32)> = Vec::new();
+        if self.anti_knight {
+            moves.extend([(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)]);
+        }
+        if self.anti_king {
+            moves.extend([(-1, -1), (-1, 1), (1, -1), (1, 1)]);
+        }
+
+        Self::offset_cells(row, col, size, &moves)
+    }
+
+    // Check the constraints that do not reduce to "different digit", i.e. that
+    // no orthogonal neighbour holds num - 1 or num + 1
+    pub fn allows(&self, board: &[Vec<Option<i32>>], row: usize, col: usize, num: i32) -> bool {
+        if !self.non_consecutive {
+            return true;
+        }
+
+        !Self::offset_cells(row, col, board.len(), &[(-1, 0), (1, 0), (0, -1), (0, 1)])
+            .iter()
+            .any(|&(r, c)| board[r][c] == Some(num - 1) || board[r][c] == Some(num + 1))
+    }
+
+    fn offset_cells(row: usize, col: usize, size: usize, moves: &[(i32, i32)]) -> Vec<(usize, usize)> {
+        let size = size as i32;
+        moves
+            .iter()
+            .map(|&(dr, dc)| (row as i32 + dr, col as i32 + dc))
+            .filter(|&(r, c)| (0..size).contains(&r) && (0..size).contains(&c))
+            .map(|(r, c)| (r as usize, c as usize))
+            .collect()
+    }
+}
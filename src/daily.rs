@@ -0,0 +1,71 @@
+use chrono::{Datelike, Local, Months, NaiveDate, Weekday};
+
+use crate::board_generator::BoardDifficulty;
+
+// The daily puzzle: one classic puzzle per calendar date, generated from a
+// seed taken from the date, so everyone playing on the same day gets the same
+// grid without going online. The difficulty follows the day of the week,
+// easy at the start of the week and expert on Sunday.
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// The date written out as a number, e.g. 20261019
+pub fn seed(date: NaiveDate) -> u64 {
+    (date.year() as u64) * 10_000 + (date.month() as u64) * 100 + date.day() as u64
+}
+
+pub fn difficulty(date: NaiveDate) -> BoardDifficulty {
+    match date.weekday() {
+        Weekday::Mon | Weekday::Tue => BoardDifficulty::Easy,
+        Weekday::Wed | Weekday::Thu => BoardDifficulty::Medium,
+        Weekday::Fri | Weekday::Sat => BoardDifficulty::Hard,
+        Weekday::Sun => BoardDifficulty::Expert,
+    }
+}
+
+// How dates are written in records.toml and the save file, e.g. "2026-10-19"
+pub fn key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+pub fn parse(key: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(key, "%Y-%m-%d").map_err(|e| format!("bad date `{}`: {}", key, e))
+}
+
+// The first day of the month the date is in
+pub fn month_of(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+// The first day of the month some months before or after the given one
+pub fn add_months(month: NaiveDate, months: i32) -> NaiveDate {
+    let month = month_of(month);
+    let shifted = if months < 0 {
+        month.checked_sub_months(Months::new(months.unsigned_abs()))
+    } else {
+        month.checked_add_months(Months::new(months as u32))
+    };
+    shifted.unwrap_or(month)
+}
+
+// The weeks of a month as the calendar shows them, Monday first, with None
+// for the days of the neighbouring months
+pub fn weeks(month: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = month_of(month);
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    for date in first.iter_days().take_while(|date| date.month() == first.month()) {
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        week[weekday] = Some(date);
+        if weekday == 6 {
+            weeks.push(week);
+            week = [None; 7];
+        }
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
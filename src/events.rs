@@ -1,248 +1,494 @@
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-use sdl2::{event::Event, keyboard::Keycode, mouse::MouseButton, EventPump};
+use chrono::NaiveDate;
+use sdl2::{event::{Event, WindowEvent}, keyboard::{Keycode, Mod}, mouse::MouseButton, rect::Rect, EventPump};
 
-use crate::board_generator;
-use crate::game::{GameState, GuiData};
+use crate::board_generator::{BoardDifficulty, BoardLayout};
+use crate::daily;
+use crate::game::{GameState, GuiData, MarkKind};
+use crate::input::{InputAction, InputEvent, Key, KeyChord};
+use crate::widgets::{self, ButtonAction};
 
 pub struct Events;
 
 impl Events {
-    pub fn process_events(
+    // Wait up to timeout_ms for input, then return everything that is queued
+    pub fn poll(event_pump: &mut EventPump, gui_data: &GuiData, timeout_ms: u32) -> Vec<InputEvent> {
+        let first = event_pump.wait_event_timeout(timeout_ms);
+        first
+            .into_iter()
+            .chain(event_pump.poll_iter())
+            .map(|event| Self::input_event(gui_data, event))
+            .collect()
+    }
+
+    // Translate an SDL event into game input
+    fn input_event(gui_data: &GuiData, event: Event) -> InputEvent {
+        // The layout is computed in drawable pixels, mouse events come in window points
+        match event {
+            Event::Quit { .. } => InputEvent::Quit,
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let (x, y) = gui_data.to_pixels(x, y);
+                InputEvent::MouseDown { x, y }
+            }
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let (x, y) = gui_data.to_pixels(x, y);
+                InputEvent::MouseUp { x, y }
+            }
+            Event::MouseMotion { mousestate, x, y, xrel, yrel, .. } => {
+                let (x, y) = gui_data.to_pixels(x, y);
+                let (dx, dy) = gui_data.to_pixels(xrel, yrel);
+                InputEvent::MouseMove { x, y, dx, dy, left: mousestate.left(), right: mousestate.right() }
+            }
+            Event::MouseWheel { x, y, .. } => InputEvent::Wheel { x, y },
+            Event::Window { win_event: WindowEvent::Leave, .. } => InputEvent::MouseLeave,
+            Event::Window { win_event: WindowEvent::FocusLost, .. } => InputEvent::FocusLost,
+            Event::KeyDown { keycode: Some(keycode), keymod, .. } => match Self::key_chord(keycode, keymod) {
+                Some(chord) => InputEvent::KeyDown(chord),
+                None => InputEvent::Other,
+            },
+            Event::KeyUp { keycode: Some(keycode), keymod, .. } => match Self::key_chord(keycode, keymod) {
+                Some(chord) => InputEvent::KeyUp(chord),
+                None => InputEvent::Other,
+            },
+            // Anything else, e.g. a resize, only needs a redraw
+            _ => InputEvent::Other,
+        }
+    }
+
+    // The chord an SDL key press stands for, None for keys that can't be bound
+    fn key_chord(keycode: Keycode, keymod: Mod) -> Option<KeyChord> {
+        Some(KeyChord {
+            key: Key::from_name(&keycode.name())?,
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LGUIMOD | Mod::RGUIMOD),
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        })
+    }
+
+    // Handle input in order, marking what has to be redrawn. Returns false
+    // once the game should quit.
+    pub fn process_input<I: IntoIterator<Item = InputEvent>>(
         game_state: &mut GameState,
-        event_pump: &mut EventPump,
-        canvas: &mut Canvas<Window>,
-        gui_data: &GuiData
+        gui_data: &GuiData,
+        events: I
     ) -> bool {
-        // I leave this part to you, as it's a long piece of code
-        for event in event_pump.poll_iter() {
-            match event {
-                // If the user closes the window or presses the escape key, exit the game
-                Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                    return false;
+        for event in events {
+            // Mouse movement only needs a redraw if it changes what is highlighted
+            // or scrolls the board, everything else redraws the whole window
+            let is_motion = matches!(event, InputEvent::MouseMove { .. });
+            let hovered_before = Self::hovered_button_rect(game_state, gui_data);
+            let scroll_before = game_state.scroll;
+            let selection_before = game_state.selected_cells.len();
+
+            if !Self::handle_event(game_state, gui_data, event) {
+                return false;
+            }
+
+            if !is_motion
+                || game_state.scroll != scroll_before
+                || game_state.selected_cells.len() != selection_before
+                || Self::hovered_button_rect(game_state, gui_data) != hovered_before
+            {
+                game_state.needs_redraw = true;
+            }
+        }
+        true
+    }
+
+    fn hovered_button_rect(game_state: &GameState, gui_data: &GuiData) -> Option<Rect> {
+        let (x, y) = game_state.mouse_position?;
+        let buttons = widgets::layout_buttons(game_state, gui_data);
+        widgets::button_at(&buttons, x, y).map(|button| button.outer_rect())
+    }
+
+    fn handle_event(game_state: &mut GameState, gui_data: &GuiData, event: InputEvent) -> bool {
+        match event {
+            // If the user closes the window, exit the game right away
+            InputEvent::Quit => {
+                return false;
+            }
+            // If the user clicks on a square, select that square
+            InputEvent::MouseDown { x, y } => {
+                game_state.mouse_position = Some((x, y));
+
+                // Buttons only fire when the mouse is released over them again
+                let buttons = widgets::layout_buttons(game_state, gui_data);
+                let pressed = widgets::button_at(&buttons, x, y).map(|button| button.action);
+                game_state.pressed_button = pressed;
+
+                // Clicks outside the open variant menu close it
+                let on_menu = matches!(
+                    pressed,
+                    Some(
+                        ButtonAction::ToggleVariantMenu |
+                        ButtonAction::ToggleAntiKnight |
+                        ButtonAction::ToggleAntiKing |
+                        ButtonAction::ToggleNonConsecutive
+                    )
+                );
+                if !on_menu {
+                    game_state.variant_menu_open = false;
                 }
-                Event::Window { win_event: sdl2::event::WindowEvent::Resized(w, h), .. } => {
-                    canvas
-                        .window_mut()
-                        .set_size(w as u32, h as u32)
-                        .unwrap();
+                if pressed.is_some() || widgets::panel_entries(game_state).is_some() {
+                    return true;
                 }
-                // If the user clicks on a square, select that square
-                Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
-                    let row = (y as i32 - gui_data.offset) / gui_data.cell_size as i32;
-                    let col = (x as i32 - gui_data.offset) / gui_data.cell_size as i32;
-                    // Check if the square is within the board
-                    if row >= 0 && row < 9 && col >= 0 && col < 9 && x >= gui_data.offset && y >= gui_data.offset {
-                        if game_state.board_initialized &&
-                           game_state.initial_board[row as usize][col as usize].is_none()
-                        {
-                            game_state.selected_square = Some((row, col));
-                        }
+
+                // Check if the square is within the board, mapping the click onto
+                // the zoomed and scrolled grid
+                if let Some((row, col)) = gui_data.cell_at(game_state, x, y) {
+                    if !Self::can_select(game_state, row, col) {
+                        return true;
                     }
-    
-                    // Check if the one of the top row buttons are pressed
-                    for index in 0..3 {
-                        let button_x_level_1 =
-                            gui_data.spacing_level_1 * (index as i32) + 4 * gui_data.offset - 15;
-                        if
-                            x >= button_x_level_1 &&
-                            x <=
-                                button_x_level_1 +
-                                    (gui_data.button_width_level_1 as i32) +
-                                    gui_data.offset &&
-                            y >= gui_data.y_level_1 - (gui_data.button_height as i32) - 10 &&
-                            y <= gui_data.y_level_1 + (gui_data.button_height as i32) + 10
-                        {
-                            match index {
-                                0 => {
-                                    game_state.new_puzzle_button_pressed = true;
-                                }
-                                1 => {
-                                    game_state.candidate_button_pressed =
-                                        !game_state.candidate_button_pressed;
-                                }
-                                2 => {
-                                    game_state.solve_button_pressed = true;
-                                }
-                                _ => {}
-                            }
-                        }
+                    // Ctrl+click adds to the selection, a plain click starts
+                    // a new one that grows while dragging
+                    if game_state.ctrl_held {
+                        game_state.toggle_selected(row as i32, col as i32);
+                    } else {
+                        game_state.select(row as i32, col as i32);
+                        game_state.drag_selecting = true;
                     }
-    
-                    for index in 0..5 {
-                        let button_x_level_2 =
-                            gui_data.spacing_level_2 * (index as i32) + 2 * gui_data.offset - 15;
-                        if
-                            x >= button_x_level_2 &&
-                            x <=
-                                button_x_level_2 +
-                                    (gui_data.button_width_level_2 as i32) +
-                                    gui_data.offset &&
-                            y >= gui_data.y_level_2 - (gui_data.button_height as i32) - 10 &&
-                            y <= gui_data.y_level_2 + (gui_data.button_height as i32) + 10
-                        {
-                            match index {
-                                0 => {
-                                    game_state.difficulty = board_generator::BoardDifficulty::Beginner;
-                                }
-                                1 => {
-                                    game_state.difficulty = board_generator::BoardDifficulty::Easy;
-                                }
-                                2 => {
-                                    game_state.difficulty = board_generator::BoardDifficulty::Medium;
-                                }
-                                3 => {
-                                    game_state.difficulty = board_generator::BoardDifficulty::Hard;
-                                }
-                                4 => {
-                                    game_state.difficulty = board_generator::BoardDifficulty::Expert;
-                                }
-                                _ => {}
-                            }
-                        }
+                    // The coloring tool paints the clicked cell
+                    if game_state.color_mode && game_state.is_selected(row, col) {
+                        let color = game_state.paint_color;
+                        game_state.edit(|game_state| game_state.paint_cell(row, col, color));
                     }
                 }
-                // If the user releases the mouse button, check if the new puzzle or solve button is pressed
-                Event::MouseButtonUp { mouse_btn: MouseButton::Left, .. } => {
-                    // Check if the new puzzle button is pressed
-                    if game_state.new_puzzle_button_pressed {
-                        game_state.selected_square = None;
-                        game_state.new_puzzle_button_pressed = false;
-    
-                        game_state.generate_new_board();
-                        game_state.board_initialized = true;
-                        game_state.puzzle_solved = false;
-                        game_state.invalid_positions = Vec::new();
-                        game_state.candidates = vec![vec![None; 9]; 9];
-                    }
-                    // Check if the solve button is pressed
-                    if game_state.solve_button_pressed {
-                        game_state.solve_button_pressed = false;
-    
-                        if game_state.board_initialized {
-                            game_state.board = game_state.solved_board.clone();
-                            game_state.invalid_positions = Vec::new();
-                        }
-                    }
+            }
+            // If the user releases the mouse button over the button it was pressed on, run its action
+            InputEvent::MouseUp { x, y } => {
+                game_state.drag_selecting = false;
+                let Some(pressed) = game_state.pressed_button.take() else {
+                    return true;
+                };
+
+                let buttons = widgets::layout_buttons(game_state, gui_data);
+                if widgets::button_at(&buttons, x, y).is_some_and(|button| button.action == pressed) {
+                    return Self::perform_action(game_state, pressed);
                 }
-                // If the user presses a key, check if it is a number and if so, add it to the board
-                Event::KeyDown { keycode: Some(keycode), .. } => {
-                    // Check if the key pressed is a number
-                    if let Some((x, y)) = game_state.selected_square {
-                        match keycode {
-                            Keycode::Num1 | Keycode::Kp1 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 1);
-                            }
-                            Keycode::Num2 | Keycode::Kp2 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 2);
-                            }
-                            Keycode::Num3 | Keycode::Kp3 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 3);
-                            }
-                            Keycode::Num4 | Keycode::Kp4 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 4);
-                            }
-                            Keycode::Num5 | Keycode::Kp5 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 5);
-                            }
-                            Keycode::Num6 | Keycode::Kp6 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 6);
-                            }
-                            Keycode::Num7 | Keycode::Kp7 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 7);
-                            }
-                            Keycode::Num8 | Keycode::Kp8 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 8);
-                            }
-                            Keycode::Num9 | Keycode::Kp9 => {
-                                Self::handle_number_entry(game_state, x as usize, y as usize, 9);
-                            }
-                            Keycode::Backspace | Keycode::Delete => {
-                                if game_state.board_initialized {
-                                    game_state.board[x as usize][y as usize] = None;
-                                    game_state.invalid_positions.retain(
-                                        |&(xi, yi, _)| (xi != x || yi != y)
-                                    );
-                                }
-                                if game_state.candidate_button_pressed {
-                                    if let Some(candidates) = &mut game_state.candidates[x as usize][y as usize] {
-                                        candidates.clear();
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-    
-                        // Check if the number entered is valid TODO: Fix this
-                        if game_state.board_initialized {
-                            if let Some(val) = game_state.board[x as usize][y as usize] {
-                                // Check if the coordinates are already in the invalid_positions
-                                let mut index = None;
-                                for (i, &(xi, yi, _)) in game_state.invalid_positions
-                                    .iter()
-                                    .enumerate() {
-                                    if xi == x && yi == y {
-                                        index = Some(i);
-                                        break;
-                                    }
-                                }
-    
-                                // If the coordinates are in the invalid_positions, check if the value is different.
-                                // If it is, remove the tuple
-                                if let Some(i) = index {
-                                    if game_state.invalid_positions[i].2 != val {
-                                        game_state.invalid_positions.remove(i);
-                                    }
-                                }
-    
-                                // Check if the move is valid. If it's not, add it to the invalid_positions
-                                if
-                                    !game_state.is_valid_move(
-                                        &game_state.board,
-                                        x as usize,
-                                        y as usize,
-                                        val
-                                    )
-                                {
-                                    game_state.invalid_positions.push((x, y, val));
-                                }
-                            }
-                        }
-    
-                        // Check if board is complete after enteirng a number
-                        if game_state.board == game_state.solved_board {
-                            game_state.selected_square = None;
-                            game_state.board = vec![vec![None; 9]; 9];
-                            game_state.initial_board = vec![vec![None; 9]; 9];
-                            game_state.solved_board = vec![vec![None; 9]; 9];
-                            game_state.board_initialized = false;
-                            game_state.puzzle_solved = true;
-                            game_state.invalid_positions = Vec::new();
-                        }
+            }
+            // Scroll the board by dragging with the right button
+            InputEvent::MouseMove { x, y, dx, dy, right: true, .. } => {
+                game_state.mouse_position = Some((x, y));
+                Self::scroll_board(game_state, gui_data, -dx, -dy);
+            }
+            // Dragging with the left button selects the cells passed over
+            InputEvent::MouseMove { x, y, left: true, .. } if game_state.drag_selecting => {
+                game_state.mouse_position = Some((x, y));
+                if let Some((row, col)) = gui_data.cell_at(game_state, x, y) {
+                    if Self::can_select(game_state, row, col) {
+                        game_state.add_to_selection(row as i32, col as i32);
                     }
                 }
-                _ => {}
             }
+            InputEvent::MouseMove { x, y, .. } => {
+                game_state.mouse_position = Some((x, y));
+            }
+            InputEvent::FocusLost => {
+                game_state.ctrl_held = false;
+                game_state.drag_selecting = false;
+            }
+            InputEvent::MouseLeave => {
+                game_state.mouse_position = None;
+            }
+            // Scroll the board with the mouse wheel
+            InputEvent::Wheel { x, y } => {
+                let step = gui_data.board_cell_size(game_state) as i32;
+                Self::scroll_board(game_state, gui_data, -x * step, -y * step);
+            }
+            InputEvent::KeyUp(chord) => {
+                game_state.ctrl_held = chord.ctrl;
+            }
+            // Keys are looked up in the key map and handled as input actions
+            InputEvent::KeyDown(chord) => {
+                game_state.ctrl_held = chord.ctrl;
+                if let Some(action) = game_state.key_map.action(chord) {
+                    return Self::perform_input(game_state, gui_data, action);
+                }
+            }
+            InputEvent::Other => {}
         }
-        return true;
+        true
     }
 
-    fn handle_number_entry(game_state: &mut GameState, x: usize, y: usize, val: i32) {
-        if game_state.candidate_button_pressed {
-            // if the number is already in the candidates, remove it otherwise add it to Vector
-            if let Some(candidates) = &mut game_state.candidates[x][y] {
-                if candidates.contains(&val) {
-                    candidates.retain(|&x| x != val);
+    // Returns false once the game should quit
+    fn perform_input(game_state: &mut GameState, gui_data: &GuiData, action: InputAction) -> bool {
+        // While the quit dialog is showing, quitting again confirms and any
+        // other action cancels
+        if game_state.quit_requested {
+            game_state.quit_requested = false;
+            return action != InputAction::Quit;
+        }
+        // A panel covers the board, so the puzzle behind it stays as it is.
+        // Only Escape and the keys that toggle a panel go through.
+        let passes_panel = matches!(action, InputAction::Quit | InputAction::OpenLibrary | InputAction::OpenCalendar);
+        if widgets::panel_entries(game_state).is_some() && !passes_panel {
+            return true;
+        }
+
+        match action {
+            InputAction::Quit => {
+                if game_state.settings_open {
+                    game_state.settings_open = false;
+                } else if game_state.library_open {
+                    game_state.library_open = false;
+                } else if game_state.calendar_open {
+                    game_state.calendar_open = false;
+                } else if game_state.variant_menu_open {
+                    game_state.variant_menu_open = false;
                 } else {
-                    candidates.push(val);
+                    game_state.quit_requested = true;
                 }
-            } else {
-                game_state.candidates[x][y] = Some(vec![val]);
             }
-        } else {
-            game_state.candidates[x][y] = None;
-            game_state.board[x][y] = Some(val);
+            InputAction::ZoomIn => {
+                Self::zoom_board(game_state, gui_data, game_state.zoom + 25);
+            }
+            InputAction::ZoomOut => {
+                Self::zoom_board(game_state, gui_data, game_state.zoom.saturating_sub(25));
+            }
+            InputAction::NextTheme => {
+                game_state.next_theme();
+            }
+            InputAction::ToggleCandidateMode => {
+                game_state.candidate_button_pressed = !game_state.candidate_button_pressed;
+            }
+            InputAction::ToggleColorMode => {
+                Self::perform_action(game_state, ButtonAction::ToggleColorMode);
+            }
+            InputAction::ClearColors => {
+                Self::perform_action(game_state, ButtonAction::ClearColors);
+            }
+            // Digits pick and paint a palette color while coloring
+            InputAction::PlaceDigit(val) | InputAction::CornerMark(val) | InputAction::CenterMark(val)
+                if game_state.color_mode =>
+            {
+                game_state.paint_selection((val - 1) as usize);
+            }
+            InputAction::Clear if game_state.color_mode => {
+                game_state.unpaint_selection();
+            }
+            InputAction::NewPuzzle => {
+                Self::perform_action(game_state, ButtonAction::NewPuzzle);
+            }
+            InputAction::OpenLibrary if game_state.library_open => {
+                game_state.library_open = false;
+            }
+            InputAction::OpenLibrary => {
+                Self::perform_action(game_state, ButtonAction::OpenLibrary);
+            }
+            InputAction::OpenCalendar if game_state.calendar_open => {
+                game_state.calendar_open = false;
+            }
+            InputAction::OpenCalendar => {
+                Self::perform_action(game_state, ButtonAction::OpenCalendar);
+            }
+            InputAction::Move(rows, cols) => {
+                game_state.move_selection(rows, cols);
+            }
+            InputAction::ExtendSelection(rows, cols) => {
+                game_state.extend_selection(rows, cols);
+            }
+            InputAction::Undo => {
+                game_state.undo();
+            }
+            InputAction::Redo => {
+                game_state.redo();
+            }
+            // Candidate mode turns plain digits into corner marks
+            InputAction::PlaceDigit(val) if game_state.candidate_button_pressed => {
+                game_state.toggle_candidate(val, MarkKind::Corner);
+            }
+            InputAction::PlaceDigit(val) => {
+                game_state.place(val);
+            }
+            InputAction::CornerMark(val) => {
+                game_state.toggle_candidate(val, MarkKind::Corner);
+            }
+            InputAction::CenterMark(val) => {
+                game_state.toggle_candidate(val, MarkKind::Center);
+            }
+            InputAction::Clear => {
+                game_state.clear();
+            }
+            InputAction::Hint => {
+                game_state.hint();
+            }
+        }
+        true
+    }
+
+    // Returns false once the game should quit
+    fn perform_action(game_state: &mut GameState, action: ButtonAction) -> bool {
+        match action {
+            ButtonAction::NewPuzzle => {
+                game_state.new_puzzle();
+            }
+            ButtonAction::ToggleCandidate => {
+                game_state.candidate_button_pressed = !game_state.candidate_button_pressed;
+            }
+            ButtonAction::Solve => {
+                game_state.solve();
+            }
+            ButtonAction::CycleLayout => {
+                game_state.layout = match game_state.layout {
+                    BoardLayout::Classic => BoardLayout::Jigsaw,
+                    BoardLayout::Jigsaw => BoardLayout::Samurai,
+                    BoardLayout::Samurai => BoardLayout::Classic,
+                };
+            }
+            ButtonAction::SetDifficulty(difficulty) => {
+                game_state.difficulty = difficulty;
+            }
+            ButtonAction::ToggleVariantMenu => {
+                game_state.variant_menu_open = !game_state.variant_menu_open;
+            }
+            ButtonAction::ToggleAntiKnight => {
+                let constraints = &mut game_state.selected_constraints;
+                constraints.anti_knight = !constraints.anti_knight;
+            }
+            ButtonAction::ToggleAntiKing => {
+                let constraints = &mut game_state.selected_constraints;
+                constraints.anti_king = !constraints.anti_king;
+            }
+            ButtonAction::ToggleNonConsecutive => {
+                let constraints = &mut game_state.selected_constraints;
+                constraints.non_consecutive = !constraints.non_consecutive;
+            }
+            ButtonAction::OpenSettings => {
+                game_state.settings_open = true;
+            }
+            ButtonAction::CycleDefaultDifficulty => {
+                let difficulty = match game_state.config.difficulty {
+                    BoardDifficulty::Beginner => BoardDifficulty::Easy,
+                    BoardDifficulty::Easy => BoardDifficulty::Medium,
+                    BoardDifficulty::Medium => BoardDifficulty::Hard,
+                    BoardDifficulty::Hard => BoardDifficulty::Expert,
+                    BoardDifficulty::Expert => BoardDifficulty::Beginner,
+                };
+                game_state.config.difficulty = difficulty;
+                game_state.difficulty = difficulty;
+            }
+            ButtonAction::CycleTheme => {
+                game_state.next_theme();
+            }
+            ButtonAction::CycleWindowSize => {
+                let window = &mut game_state.config.window;
+                window.height_percent = if window.height_percent >= 100 {
+                    60
+                } else {
+                    (window.height_percent / 10 + 1) * 10
+                };
+            }
+            ButtonAction::ToggleAnimations => {
+                game_state.config.animations = !game_state.config.animations;
+            }
+            ButtonAction::SaveSettings => {
+                if let Err(e) = game_state.config.save() {
+                    eprintln!("Could not save settings: {}", e);
+                }
+                game_state.settings_open = false;
+            }
+            ButtonAction::CloseSettings => {
+                game_state.settings_open = false;
+            }
+            ButtonAction::OpenLibrary => {
+                game_state.settings_open = false;
+                game_state.library_open = true;
+                game_state.library_page = 0;
+            }
+            ButtonAction::CycleLibraryFilter => {
+                game_state.library_filter = match game_state.library_filter {
+                    None => Some(BoardDifficulty::Beginner),
+                    Some(BoardDifficulty::Beginner) => Some(BoardDifficulty::Easy),
+                    Some(BoardDifficulty::Easy) => Some(BoardDifficulty::Medium),
+                    Some(BoardDifficulty::Medium) => Some(BoardDifficulty::Hard),
+                    Some(BoardDifficulty::Hard) => Some(BoardDifficulty::Expert),
+                    Some(BoardDifficulty::Expert) => None,
+                };
+                game_state.library_page = 0;
+            }
+            ButtonAction::LibraryPage(pages) => {
+                let count = game_state.library.filtered(game_state.library_filter).len();
+                let last = count.saturating_sub(1) / widgets::LIBRARY_PAGE;
+                game_state.library_page = game_state.library_page.saturating_add_signed(pages as isize).min(last);
+            }
+            ButtonAction::OpenLibraryPuzzle(index) => {
+                if let Err(e) = game_state.open_library_puzzle(index) {
+                    eprintln!("Could not open the puzzle: {}", e);
+                }
+            }
+            ButtonAction::CloseLibrary => {
+                game_state.library_open = false;
+            }
+            ButtonAction::OpenCalendar => {
+                game_state.settings_open = false;
+                game_state.calendar_open = true;
+                game_state.calendar_month = daily::month_of(daily::today());
+            }
+            ButtonAction::CalendarMonth(0) => {
+                game_state.calendar_month = daily::month_of(daily::today());
+            }
+            ButtonAction::CalendarMonth(months) => {
+                game_state.calendar_month = daily::add_months(game_state.calendar_month, months);
+            }
+            ButtonAction::OpenDaily(days) => {
+                if let Some(date) = NaiveDate::from_num_days_from_ce_opt(days) {
+                    game_state.start_daily(date);
+                }
+            }
+            ButtonAction::CloseCalendar => {
+                game_state.calendar_open = false;
+            }
+            ButtonAction::CloseSummary => {
+                game_state.close_completion();
+            }
+            ButtonAction::Share => {
+                game_state.clipboard = game_state.share();
+            }
+            ButtonAction::ConfirmQuit => {
+                return false;
+            }
+            ButtonAction::CancelQuit => {
+                game_state.quit_requested = false;
+            }
+            ButtonAction::ToggleColorMode => {
+                game_state.color_mode = !game_state.color_mode;
+                // The palette takes the place of the variant button
+                game_state.variant_menu_open = false;
+            }
+            ButtonAction::PickColor(color) => {
+                game_state.paint_color = color;
+            }
+            ButtonAction::ClearColors => {
+                game_state.edit(|game_state| game_state.clear_colors());
+            }
         }
+        true
+    }
+
+    // Zoom between 100% and 300%, keeping the centre of the visible area in place
+    fn zoom_board(game_state: &mut GameState, gui_data: &GuiData, zoom: u32) {
+        let zoom = zoom.clamp(100, 300);
+        let half = (gui_data.board_pixels() / 2) as i32;
+        let (scroll_x, scroll_y) = game_state.scroll;
+
+        game_state.scroll = (
+            (scroll_x + half) * (zoom as i32) / (game_state.zoom as i32) - half,
+            (scroll_y + half) * (zoom as i32) / (game_state.zoom as i32) - half
+        );
+        game_state.zoom = zoom;
+        Self::scroll_board(game_state, gui_data, 0, 0);
+    }
+
+    pub fn scroll_board(game_state: &mut GameState, gui_data: &GuiData, dx: i32, dy: i32) {
+        let max_scroll = gui_data.max_scroll(game_state);
+        game_state.scroll = (
+            (game_state.scroll.0 + dx).clamp(0, max_scroll),
+            (game_state.scroll.1 + dy).clamp(0, max_scroll)
+        );
+    }
+
+    // Clicks select cells without a given, except for the coloring tool
+    // which paints any cell
+    fn can_select(game_state: &GameState, row: usize, col: usize) -> bool {
+        game_state.board_initialized && (game_state.color_mode || game_state.initial_board[row][col].is_none())
     }
-    
 }
@@ -0,0 +1,69 @@
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+
+use crate::config::{self, FontConfig};
+use crate::game::GuiData;
+
+// Fonts built into the binary, so the game doesn't depend on the working
+// directory. The config file can point at other font files instead.
+const REGULAR_FONT: &[u8] = include_bytes!("../assets/fonts/LibreFranklin-Medium.ttf");
+const BOLD_FONT: &[u8] = include_bytes!("../assets/fonts/LibreFranklin-Bold.ttf");
+
+// Try the custom fonts from the config once at startup. A font that can't be
+// loaded is reported and left out of the returned settings, so the built-in
+// one is used instead.
+pub fn usable_fonts(ttf_context: &Sdl2TtfContext, font_config: &FontConfig) -> FontConfig {
+    let mut usable = font_config.clone();
+    for (setting, custom) in [("regular", &mut usable.regular), ("bold", &mut usable.bold)] {
+        let Some(path) = custom.as_deref() else {
+            continue;
+        };
+        let resolved = config::resolve_path(path);
+        if let Err(e) = ttf_context.load_font(&resolved, 12) {
+            eprintln!(
+                "Could not load the {} font \"{}\" set in the config file: {}. Using the built-in font instead.",
+                setting,
+                resolved.display(),
+                e
+            );
+            *custom = None;
+        }
+    }
+    usable
+}
+
+// Load every font the game uses at the sizes of the current layout, in the
+// order given by the glyph_cache FONT_* constants
+pub fn load_fonts<'ttf>(
+    ttf_context: &'ttf Sdl2TtfContext,
+    font_config: &FontConfig,
+    gui_data: &GuiData
+) -> Result<Vec<Font<'ttf, 'static>>, String> {
+    let regular = |size| load_font(ttf_context, font_config.regular.as_deref(), REGULAR_FONT, size);
+    let bold = |size| load_font(ttf_context, font_config.bold.as_deref(), BOLD_FONT, size);
+
+    Ok(vec![
+        regular(gui_data.font_size_buttons)?,
+        bold(gui_data.font_size_numbers)?,
+        bold(gui_data.font_size_message)?,
+        regular(gui_data.font_size_candidates)?,
+        regular(gui_data.font_size_numbers)?,
+    ])
+}
+
+fn load_font<'ttf>(
+    ttf_context: &'ttf Sdl2TtfContext,
+    custom: Option<&str>,
+    embedded: &'static [u8],
+    size: u16
+) -> Result<Font<'ttf, 'static>, String> {
+    match custom {
+        Some(path) => {
+            let resolved = config::resolve_path(path);
+            ttf_context
+                .load_font(&resolved, size)
+                .map_err(|e| format!("could not load font \"{}\": {}", resolved.display(), e))
+        }
+        None => ttf_context.load_font_from_rwops(RWops::from_bytes(embedded)?, size),
+    }
+}
@@ -1,22 +1,51 @@
 extern crate sdl2;
 
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDate;
+use sdl2::rect::Rect;
+use sdl2::EventPump;
+use serde::{Deserialize, Serialize};
 
 use events::Events;
 use screen_renderer::ScreenRenderer;
 
-use crate::{events, screen_renderer, board_generator};
+use crate::{animation, events, screen_renderer, board_generator, constraints, regions, solver, widgets};
+use crate::glyph_cache::GlyphCache;
+use crate::config::{self, Config};
+use crate::fonts;
+use crate::input::KeyMap;
+use crate::daily;
+use crate::library::Library;
+use crate::puzzle_queue::{GeneratedPuzzle, PuzzleKind, PuzzleQueue};
+use crate::replay::Recorder;
+use crate::save::{Records, SavedGame};
+use crate::sdl_context::SdlContext;
+use crate::theme::{Theme, PALETTE_SIZE};
+
+// How long the game loop sleeps waiting for input before checking in again
+const IDLE_TIMEOUT_MS: u32 = 1000;
+// How long it waits between frames while something is animating
+const FRAME_TIMEOUT_MS: u32 = 16;
 
 pub struct GuiData {
+    pub window_width: u32,
+    pub window_height: u32,
+    // Drawable pixels per window point, above 1.0 on high-DPI displays
+    pub dpi_scale: f32,
+    pub landscape: bool,
     pub cell_size: u32,
     pub offset: i32,
-    pub button_width_level_1: u32,
-    pub button_width_level_2: u32,
-    pub button_height: u32,
-    pub y_level_1: i32,
-    pub y_level_2: i32,
-    pub spacing_level_1: i32,
-    pub spacing_level_2: i32,
+    pub board_x: i32,
+    pub board_y: i32,
+    pub buttons_level_1: Vec<Rect>,
+    pub buttons_level_2: Vec<Rect>,
+    pub menu_x: i32,
+    pub menu_y: i32,
+    pub menu_width: u32,
+    pub menu_entry_height: u32,
     pub font_size_buttons: u16,
     pub font_size_numbers: u16,
     pub font_size_message: u16,
@@ -24,87 +53,1188 @@ pub struct GuiData {
 }
 
 impl GuiData {
-    fn new(window_width: u32, _window_height: u32) -> Self {
-        let grid_size = ((window_width as f32) * 0.95) as u32; // f32 used to handle fractional results
+    // Compute the layout for a drawable area of window_width x window_height
+    // pixels. Tall windows get the buttons below the board, wide windows get
+    // them in a panel to the right of it.
+    pub fn new(window_width: u32, window_height: u32, dpi_scale: f32) -> Self {
+        let number_of_buttons_level_1 = 6;
+        let number_of_buttons_level_2 = 6;
+
+        let landscape = window_width > window_height;
+        let offset = ((window_width.min(window_height) as f32) * 0.025).max(4.0) as i32;
+        let width = window_width as i32 - 2 * offset;
+        let height = window_height as i32 - 2 * offset;
+
+        // Buttons are 5% and gaps 3% of the board size. In portrait the board
+        // has to leave room for two rows of buttons below it.
+        let grid_size = if landscape {
+            (height as f32).min((width as f32) * 0.62)
+        } else {
+            (width as f32).min((height as f32) / 1.19)
+        }.max(90.0) as u32; // f32 used to handle fractional results
         let cell_size = grid_size / 9;
-        let offset = ((window_width - grid_size) / 2) as i32;
+        let grid_size = cell_size * 9;
+        let button_height = ((grid_size as f32) * 0.05).max(20.0) as u32;
+        let gap = ((grid_size as f32) * 0.03) as i32;
+
+        let (board_x, board_y, buttons_level_1, buttons_level_2) = if landscape {
+            let board_x = offset;
+            let board_y = offset + (height - grid_size as i32) / 2;
+            let panel_x = board_x + (grid_size as i32) + 2 * gap;
+            let panel_width = window_width as i32 - panel_x - offset;
 
-        let button_width_level_1 = 2 * (cell_size - (offset as u32));
-        let button_width_level_2 = 2 * cell_size - 4 * (offset as u32);
-        let button_height = cell_size / 8;
-        let y_level_1 = ((grid_size + cell_size) as i32) - offset;
-        let y_level_2 = ((1.25 * (cell_size as f32)) as i32) + (grid_size as i32) + 2 * offset;
+            let buttons_level_1 = Self::button_grid(
+                panel_x, board_y, panel_width, button_height, gap, 2, number_of_buttons_level_1
+            );
+            let level_2_y = buttons_level_1.last().map_or(board_y, |rect| rect.bottom()) + 2 * gap;
+            let buttons_level_2 = Self::button_grid(
+                panel_x, level_2_y, panel_width, button_height, gap, 2, number_of_buttons_level_2
+            );
+            (board_x, board_y, buttons_level_1, buttons_level_2)
+        } else {
+            let board_x = (window_width as i32 - grid_size as i32) / 2;
+            let board_y = offset;
 
-        let number_of_buttons_level_1 = 3;
-        let number_of_buttons_level_2 = 5;
-        let spacing_level_1 = ((window_width as i32) - 2 * offset) / number_of_buttons_level_1;
-        let spacing_level_2 = ((window_width as i32) - 2 * offset) / number_of_buttons_level_2 + 4;
+            let level_1_y = board_y + (grid_size as i32) + gap;
+            let buttons_level_1 = Self::button_grid(
+                board_x, level_1_y, grid_size as i32, button_height, gap,
+                number_of_buttons_level_1, number_of_buttons_level_1
+            );
+            let level_2_y = level_1_y + (button_height as i32) + gap;
+            let buttons_level_2 = Self::button_grid(
+                board_x, level_2_y, grid_size as i32, button_height, gap,
+                number_of_buttons_level_2, number_of_buttons_level_2
+            );
+            (board_x, board_y, buttons_level_1, buttons_level_2)
+        };
 
-        let font_size_buttons = 20;
-        let font_size_numbers = (cell_size / 2) as u16;
-        let font_size_message = (cell_size / 2) as u16;
-        let font_size_candidates = (cell_size / 4) as u16;
+        // The variant menu opens upwards, right above the variant button
+        let number_of_menu_entries = 3;
+        let variant_button = buttons_level_2[number_of_buttons_level_2 - 1];
+        let menu_width = 3 * cell_size;
+        let menu_entry_height = button_height + 8;
+        let menu_x = (variant_button.right() - (menu_width as i32)).max(offset);
+        let menu_y = variant_button.y() - gap - (number_of_menu_entries * menu_entry_height) as i32;
+
+        let font_size_buttons = (button_height / 2).max(8) as u16;
+        let font_size_numbers = (cell_size / 2).max(8) as u16;
+        let font_size_message = (cell_size / 2).max(8) as u16;
+        let font_size_candidates = (cell_size / 4).max(6) as u16;
 
         GuiData {
+            window_width,
+            window_height,
+            dpi_scale,
+            landscape,
             cell_size,
             offset,
-            button_width_level_1,
-            button_width_level_2,
-            button_height,
-            y_level_1,
-            y_level_2,
-            spacing_level_1,
-            spacing_level_2,
+            board_x,
+            board_y,
+            buttons_level_1,
+            buttons_level_2,
+            menu_x,
+            menu_y,
+            menu_width,
+            menu_entry_height,
             font_size_buttons,
             font_size_numbers,
             font_size_message,
             font_size_candidates
         }
     }
+
+    // Lay out count buttons of equal size in rows of columns, filling width
+    fn button_grid(
+        x: i32,
+        y: i32,
+        width: i32,
+        button_height: u32,
+        gap: i32,
+        columns: usize,
+        count: usize
+    ) -> Vec<Rect> {
+        let button_width = ((width - (columns as i32 - 1) * gap) / (columns as i32)).max(1) as u32;
+        (0..count)
+            .map(|index| {
+                Rect::new(
+                    x + ((index % columns) as i32) * ((button_width as i32) + gap),
+                    y + ((index / columns) as i32) * ((button_height as i32) + gap),
+                    button_width,
+                    button_height
+                )
+            })
+            .collect()
+    }
+
+    // Rows of the settings screen, centred over the board
+    pub fn settings_entries(&self, count: usize) -> Vec<Rect> {
+        let width = self.board_pixels() * 2 / 3;
+        let pitch = (self.menu_entry_height as i32) + self.offset;
+        let x = self.board_x + ((self.board_pixels() - width) / 2) as i32;
+        let y = self.board_y + ((self.board_pixels() as i32) - pitch * (count as i32)) / 2;
+        (0..count)
+            .map(|index| Rect::new(x, y + (index as i32) * pitch, width, self.menu_entry_height - 8))
+            .collect()
+    }
+
+    // The three buttons of the completion screen, each taking the place of
+    // two regular buttons
+    pub fn completion_buttons(&self) -> Vec<Rect> {
+        self.buttons_level_1.chunks(2).map(Self::bounds).collect()
+    }
+
+    // Where the summary of a finished puzzle goes, in place of the second
+    // group of buttons
+    pub fn summary_area(&self) -> Rect {
+        Self::bounds(&self.buttons_level_2)
+    }
+
+    // The smallest rectangle around all of them
+    fn bounds(rects: &[Rect]) -> Rect {
+        let left = rects.iter().map(|rect| rect.left()).min().unwrap_or_default();
+        let top = rects.iter().map(|rect| rect.top()).min().unwrap_or_default();
+        let right = rects.iter().map(|rect| rect.right()).max().unwrap_or_default();
+        let bottom = rects.iter().map(|rect| rect.bottom()).max().unwrap_or_default();
+        Rect::new(left, top, (right - left).max(1) as u32, (bottom - top).max(1) as u32)
+    }
+
+    // Background panel behind the settings screen rows
+    pub fn settings_panel(&self, count: usize) -> Rect {
+        let entries = self.settings_entries(count);
+        let (first, last) = (entries[0], entries[count - 1]);
+        Rect::new(
+            first.x() - self.offset,
+            first.y() - self.offset,
+            first.width() + 2 * (self.offset as u32),
+            (last.bottom() - first.y() + 2 * self.offset) as u32
+        )
+    }
+
+    // Convert mouse coordinates from window points to drawable pixels
+    pub fn to_pixels(&self, x: i32, y: i32) -> (i32, i32) {
+        (((x as f32) * self.dpi_scale) as i32, ((y as f32) * self.dpi_scale) as i32)
+    }
+
+    // Width and height of the visible board area in pixels
+    pub fn board_pixels(&self) -> u32 {
+        self.cell_size * 9
+    }
+
+    // The visible board area including its outer border
+    pub fn board_area(&self) -> Rect {
+        Rect::new(self.board_x, self.board_y, self.board_pixels() + 3, self.board_pixels() + 3)
+    }
+
+    // Size of a single cell of the current board, which shrinks for boards
+    // larger than 9x9 and grows with the zoom level
+    pub fn board_cell_size(&self, game_state: &GameState) -> u32 {
+        self.board_pixels() * game_state.zoom / (100 * game_state.regions.size() as u32)
+    }
+
+    // Screen rectangle of a cell, taking zoom and scroll into account
+    pub fn cell_rect(&self, game_state: &GameState, row: usize, col: usize) -> Rect {
+        let cell_size = self.board_cell_size(game_state);
+        Rect::new(
+            (col as i32) * (cell_size as i32) + self.board_x - game_state.scroll.0,
+            (row as i32) * (cell_size as i32) + self.board_y - game_state.scroll.1,
+            cell_size,
+            cell_size
+        )
+    }
+
+    // Map a click to the active cell under it, if any
+    pub fn cell_at(&self, game_state: &GameState, x: i32, y: i32) -> Option<(usize, usize)> {
+        let board_pixels = self.board_pixels() as i32;
+        if x < self.board_x || y < self.board_y || x >= self.board_x + board_pixels || y >= self.board_y + board_pixels {
+            return None;
+        }
+
+        let cell_size = self.board_cell_size(game_state) as i32;
+        let row = ((y - self.board_y + game_state.scroll.1) / cell_size) as usize;
+        let col = ((x - self.board_x + game_state.scroll.0) / cell_size) as usize;
+        let size = game_state.regions.size();
+        if row < size && col < size && game_state.regions.is_active(row, col) {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+
+    // Largest scroll offset that still keeps the board inside the visible area
+    pub fn max_scroll(&self, game_state: &GameState) -> i32 {
+        let content = self.board_cell_size(game_state) * (game_state.regions.size() as u32);
+        (content as i32 - self.board_pixels() as i32).max(0)
+    }
+}
+
+// Where a digit on the board came from, each is drawn in its own style
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigitSource {
+    Given,
+    Player,
+    Hint,
+    Solver,
+}
+
+// What a call into the GameState API changed, so front ends can react to it
+// without knowing the rules
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    SelectionChanged,
+    DigitPlaced { row: usize, col: usize, digit: i32 },
+    // A placed digit breaks a rule, it stays on the board marked as a conflict
+    Conflict { row: usize, col: usize, digit: i32 },
+    CellCleared { row: usize, col: usize },
+    MarksChanged { row: usize, col: usize },
+    HintPlaced { row: usize, col: usize },
+    NewPuzzle,
+    // The solver filled in the rest of the board
+    Solved,
+    // The player's board is full and breaks no rule, but is not the solution
+    // the puzzle was made with. Comes right before Completed.
+    AlternateSolution,
+    // The player finished the puzzle
+    Completed,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MarkKind {
+    Corner,
+    Center,
+}
+
+// How long the completion wave takes to run across the board
+const COMPLETION_ANIMATION: Duration = Duration::from_millis(1600);
+
+// How a finished puzzle went, shown on the completion screen
+#[derive(Clone, PartialEq, Debug)]
+pub struct Completion {
+    pub time: Duration,
+    pub mistakes: u32,
+    pub hints: u32,
+    pub difficulty: board_generator::BoardDifficulty,
+    pub layout: board_generator::BoardLayout,
+    // Best time from before this puzzle, None the first time
+    pub previous_best: Option<Duration>,
+    // Time since the puzzle was finished, drives the animation
+    pub age: Duration,
+    // The player found a solution other than the stored one
+    pub alternate_solution: bool,
+    // The result was copied for sharing
+    pub shared: bool,
+    // Date of the daily puzzle, and the days in a row it makes
+    pub daily: Option<NaiveDate>,
+    pub streak: u32,
+}
+
+impl Completion {
+    pub fn is_new_best(&self) -> bool {
+        self.previous_best.is_none_or(|best| self.time < best)
+    }
+
+    // Lines of the summary panel
+    pub fn summary(&self) -> Vec<String> {
+        let plural = |count: u32, word: &str| format!("{} {}{}", count, word, if count == 1 { "" } else { "s" });
+        let best = match self.previous_best {
+            _ if self.daily.is_some() => format!("Daily streak: {}", plural(self.streak, "day")),
+            Some(_) if self.is_new_best() => "New personal best!".to_string(),
+            Some(best) => format!("Personal best {}", format_time(best)),
+            None => "First one solved!".to_string(),
+        };
+        vec![
+            format!("Solved in {}", format_time(self.time)),
+            format!("{}, {}", plural(self.mistakes, "mistake"), plural(self.hints, "hint")),
+            match (self.daily, self.alternate_solution) {
+                (Some(date), _) => format!("Daily {}, {:?}", daily::key(date), self.difficulty),
+                (None, true) => format!("{:?} {:?}, a solution of your own", self.difficulty, self.layout),
+                (None, false) => format!("{:?} {:?}", self.difficulty, self.layout),
+            },
+            best,
+        ]
+    }
+}
+
+// What the daily puzzle of a date is made as
+fn daily_kind(date: NaiveDate) -> PuzzleKind {
+    PuzzleKind {
+        difficulty: daily::difficulty(date),
+        layout: board_generator::BoardLayout::Classic,
+        constraints: constraints::Constraints::default(),
+    }
+}
+
+// Minutes and seconds, e.g. "4:07"
+pub fn format_time(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
 }
 
 //GameState struct to store all relevant game state information
 pub struct GameState {
+    // The cell the arrow keys move from, always one of selected_cells
     pub selected_square: Option<(i32, i32)>,
-    pub new_puzzle_button_pressed: bool,
-    pub solve_button_pressed: bool,
+    // Every selected cell, edits apply to all of them at once
+    pub selected_cells: Vec<(i32, i32)>,
+    // The left button went down on a cell and is still held, cells it moves
+    // over join the selection
+    pub drag_selecting: bool,
+    // Ctrl (or Cmd) is held, clicks then add cells to the selection
+    pub ctrl_held: bool,
+    // Time spent on the current puzzle
+    pub elapsed: Duration,
+    // Button the left mouse button went down on, it fires if released over it
+    pub pressed_button: Option<widgets::ButtonAction>,
+    // Last known mouse position in drawable pixels, used for hover highlights
+    pub mouse_position: Option<(i32, i32)>,
     pub candidate_button_pressed: bool,
+    pub variant_menu_open: bool,
     pub board: Vec<Vec<Option<i32>>>,
     pub initial_board: Vec<Vec<Option<i32>>>,
     pub solved_board: Vec<Vec<Option<i32>>>,
-    pub candidates: Vec<Vec<Option<Vec<i32>>>>,
+    // Who filled in each non-given cell, only meaningful where board has a digit
+    pub entry_sources: Vec<Vec<DigitSource>>,
+    // Pencil marks: corner marks note where a digit can go in a box, center
+    // marks the digits a cell can still hold
+    pub corner_marks: Vec<Vec<Option<Vec<i32>>>>,
+    pub center_marks: Vec<Vec<Option<Vec<i32>>>>,
+    // Palette index each cell is painted with by the coloring tool
+    pub cell_colors: Vec<Vec<Option<usize>>>,
+    // While coloring, clicks and digit keys paint cells instead of filling them
+    pub color_mode: bool,
+    pub paint_color: usize,
     pub board_initialized: bool,
+    // The last puzzle was finished, its board stays on screen next to the summary
     pub puzzle_solved: bool,
+    pub completion: Option<Completion>,
+    // Conflicting digits placed and hints taken on the current puzzle
+    pub mistakes: u32,
+    pub hints_used: u32,
+    pub records: Records,
+    // Text a front end should put on the clipboard
+    pub clipboard: Option<String>,
+    // Events since the front end last took them, for animations
+    pub recent_events: Vec<GameEvent>,
+    pub library: Library,
+    // The puzzle being played came from the library, see library::LibraryPuzzle::key
+    pub library_key: Option<String>,
+    // The library browser is open, showing a page of the puzzles of a difficulty
+    pub library_open: bool,
+    pub library_filter: Option<board_generator::BoardDifficulty>,
+    pub library_page: usize,
+    // The puzzle being played is the daily puzzle of this date
+    pub daily: Option<NaiveDate>,
+    // The calendar of daily puzzles is open, showing the month of this date
+    pub calendar_open: bool,
+    pub calendar_month: NaiveDate,
+    // Makes puzzles on a worker thread. Without one they are made on the spot.
+    pub puzzle_queue: Option<PuzzleQueue>,
+    // Time spent waiting for the worker to finish a puzzle
+    pub loading: Option<Duration>,
     pub difficulty: board_generator::BoardDifficulty,
+    pub layout: board_generator::BoardLayout,
+    pub regions: regions::Regions,
+    pub selected_constraints: constraints::Constraints,
+    pub constraints: constraints::Constraints,
+    // Checks moves against the regions and constraints above, see set_rules
+    solver: solver::Solver,
     pub invalid_positions: Vec<(i32, i32, i32)>,
+    pub zoom: u32,
+    pub scroll: (i32, i32),
+    // Whether anything changed since the last frame, nothing is drawn
+    // otherwise
+    pub needs_redraw: bool,
+    // Built-in themes followed by any custom ones, switched with the T key
+    pub themes: Vec<Theme>,
+    pub theme_index: usize,
+    pub config: Config,
+    pub settings_open: bool,
+    pub key_map: KeyMap,
+    // Escape was pressed and the quit dialog is showing
+    pub quit_requested: bool,
+    history: Vec<Snapshot>,
+    future: Vec<Snapshot>,
+}
+
+// The part of the game state an undo step restores
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    board: Vec<Vec<Option<i32>>>,
+    corner_marks: Vec<Vec<Option<Vec<i32>>>>,
+    center_marks: Vec<Vec<Option<Vec<i32>>>>,
+    cell_colors: Vec<Vec<Option<usize>>>,
+    entry_sources: Vec<Vec<DigitSource>>,
+    invalid_positions: Vec<(i32, i32, i32)>,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameState {
-    fn new() -> Self {
+    pub fn new() -> Self {
         GameState {
             selected_square: None,
-            new_puzzle_button_pressed: false,
-            solve_button_pressed: false,
+            selected_cells: Vec::new(),
+            drag_selecting: false,
+            ctrl_held: false,
+            elapsed: Duration::ZERO,
+            pressed_button: None,
+            mouse_position: None,
             candidate_button_pressed: false,
+            variant_menu_open: false,
             board: vec![vec![None; 9]; 9],
             initial_board: vec![vec![None; 9]; 9],
             solved_board: vec![vec![None; 9]; 9],
-            candidates: vec![vec![None; 9]; 9],
+            entry_sources: vec![vec![DigitSource::Player; 9]; 9],
+            corner_marks: vec![vec![None; 9]; 9],
+            center_marks: vec![vec![None; 9]; 9],
+            cell_colors: vec![vec![None; 9]; 9],
+            color_mode: false,
+            paint_color: 0,
             board_initialized: false,
             puzzle_solved: false,
+            completion: None,
+            mistakes: 0,
+            hints_used: 0,
+            records: Records::default(),
+            clipboard: None,
+            recent_events: Vec::new(),
+            library: Library::default(),
+            library_key: None,
+            library_open: false,
+            library_filter: None,
+            library_page: 0,
+            daily: None,
+            calendar_open: false,
+            calendar_month: daily::month_of(daily::today()),
+            puzzle_queue: None,
+            loading: None,
             difficulty: board_generator::BoardDifficulty::Medium,
+            layout: board_generator::BoardLayout::Classic,
+            regions: regions::Regions::classic(),
+            selected_constraints: constraints::Constraints::default(),
+            constraints: constraints::Constraints::default(),
+            solver: solver::Solver::new(regions::Regions::classic()),
             invalid_positions: Vec::new(),
+            zoom: 100,
+            scroll: (0, 0),
+            needs_redraw: false,
+            themes: Theme::built_in(),
+            theme_index: 0,
+            config: Config::default(),
+            settings_open: false,
+            key_map: KeyMap::new(&BTreeMap::new()),
+            quit_requested: false,
+            history: Vec::new(),
+            future: Vec::new(),
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.themes[self.theme_index]
+    }
+
+    pub fn next_theme(&mut self) {
+        self.theme_index = (self.theme_index + 1) % self.themes.len();
+        self.config.theme = self.theme().name.clone();
+    }
+
+    // Switch to the theme with the given name, ignoring case. Returns false
+    // if there is no such theme.
+    pub fn select_theme(&mut self, name: &str) -> bool {
+        match self.themes.iter().position(|theme| theme.name.eq_ignore_ascii_case(name)) {
+            Some(index) => {
+                self.theme_index = index;
+                true
+            }
+            None => false,
         }
     }
 
+    // Use the settings from a config file
+    pub fn apply_config(&mut self, config: Config) {
+        self.difficulty = config.difficulty;
+        self.key_map = KeyMap::new(&config.keys);
+        if !self.select_theme(&config.theme) {
+            eprintln!("Unknown theme {}, using {}", config.theme, self.theme().name);
+        }
+        self.config = config;
+    }
+
     pub fn generate_new_board(&mut self) {
-        // create a new board generator
-        let mut board_generator = board_generator::BoardGenerator::new(self.difficulty.clone());
+        let puzzle = GeneratedPuzzle::generate(self.next_kind(), &AtomicBool::new(false)).unwrap();
+        self.daily = None;
+        self.use_puzzle(puzzle);
+    }
 
-        // generate a new board
-        self.board = board_generator.generate_sudoku();
-        self.solved_board = board_generator.get_solved_board().unwrap();
+    // The kind of puzzle the New Puzzle button starts
+    pub fn next_kind(&self) -> PuzzleKind {
+        PuzzleKind {
+            difficulty: self.difficulty,
+            layout: self.layout,
+            constraints: self.selected_constraints,
+        }
+    }
+
+    // Set up the board of a newly generated puzzle
+    fn use_puzzle(&mut self, puzzle: GeneratedPuzzle) {
+        self.board = puzzle.board;
+        self.solved_board = puzzle.solution;
         self.initial_board = self.board.clone();
+        self.entry_sources = vec![vec![DigitSource::Player; self.board.len()]; self.board.len()];
+        self.cell_colors = vec![vec![None; self.board.len()]; self.board.len()];
+        self.set_rules(puzzle.regions, puzzle.constraints);
+        self.zoom = 100;
+        self.scroll = (0, 0);
+        self.clear_history();
+    }
+
+    // Switch to the regions and constraints of another puzzle. Setting up
+    // the solver takes a while, so it is only done here rather than for every
+    // move checked.
+    pub fn set_rules(&mut self, regions: regions::Regions, constraints: constraints::Constraints) {
+        self.solver = solver::Solver::new_with_constraints(regions.clone(), constraints);
+        self.regions = regions;
+        self.constraints = constraints;
+    }
+
+    // Run an edit of the board, remembering the previous state for undo if
+    // the edit changed anything
+    pub fn edit<F: FnOnce(&mut GameState)>(&mut self, edit: F) {
+        let before = self.snapshot();
+        edit(self);
+        if self.snapshot() != before {
+            self.history.push(before);
+            self.future.clear();
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.history.pop() else {
+            return false;
+        };
+        self.future.push(self.snapshot());
+        self.restore(snapshot);
+        self.check_completion_after_history();
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.future.pop() else {
+            return false;
+        };
+        self.history.push(self.snapshot());
+        self.restore(snapshot);
+        self.check_completion_after_history();
+        true
+    }
+
+    // Stepping through the history can fill the board as well. Snapshots keep
+    // the digit sources, so redoing Solve is still no finish, see
+    // solver_filled.
+    fn check_completion_after_history(&mut self) {
+        let mut events = Vec::new();
+        self.check_completion(&mut events);
+        self.emit(events);
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.future.clear();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board.clone(),
+            corner_marks: self.corner_marks.clone(),
+            center_marks: self.center_marks.clone(),
+            cell_colors: self.cell_colors.clone(),
+            entry_sources: self.entry_sources.clone(),
+            invalid_positions: self.invalid_positions.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.board = snapshot.board;
+        self.corner_marks = snapshot.corner_marks;
+        self.center_marks = snapshot.center_marks;
+        self.cell_colors = snapshot.cell_colors;
+        self.entry_sources = snapshot.entry_sources;
+        self.invalid_positions = snapshot.invalid_positions;
+    }
+
+    // Paint a cell with a palette color, painting it again with the same
+    // color removes it
+    pub fn paint_cell(&mut self, row: usize, col: usize, color: usize) {
+        self.paint_cells(&[(row, col)], color);
+    }
+
+    // Paint several cells at once. The color is removed from all of them
+    // only if every one already has it.
+    pub fn paint_cells(&mut self, cells: &[(usize, usize)], color: usize) {
+        let remove = cells.iter().all(|&(row, col)| self.cell_colors[row][col] == Some(color));
+        for &(row, col) in cells {
+            self.cell_colors[row][col] = if remove { None } else { Some(color) };
+        }
+    }
+
+    pub fn clear_colors(&mut self) {
+        for row in self.cell_colors.iter_mut() {
+            row.fill(None);
+        }
+    }
+
+    // Paint the selected cells as one undo step and make the color the one
+    // clicks paint with
+    pub fn paint_selection(&mut self, color: usize) {
+        if !self.board_initialized || color >= PALETTE_SIZE {
+            return;
+        }
+        let cells = self.selected_cells();
+        self.paint_color = color;
+        self.edit(|game_state| game_state.paint_cells(&cells, color));
+    }
+
+    pub fn unpaint_selection(&mut self) {
+        if !self.board_initialized {
+            return;
+        }
+        let cells = self.selected_cells();
+        self.edit(|game_state| {
+            for (row, col) in cells {
+                game_state.cell_colors[row][col] = None;
+            }
+        });
+    }
+
+    fn selected_cells(&self) -> Vec<(usize, usize)> {
+        self.selected_cells.iter().map(|&(row, col)| (row as usize, col as usize)).collect()
+    }
+
+    // Select a single cell, dropping the rest of the selection
+    pub fn select(&mut self, row: i32, col: i32) -> Vec<GameEvent> {
+        self.selected_cells = vec![(row, col)];
+        self.selected_square = Some((row, col));
+        self.emit(vec![GameEvent::SelectionChanged])
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selected_cells.clear();
+        self.selected_square = None;
+    }
+
+    pub fn add_to_selection(&mut self, row: i32, col: i32) {
+        if !self.selected_cells.contains(&(row, col)) {
+            self.selected_cells.push((row, col));
+        }
+        self.selected_square = Some((row, col));
+    }
+
+    // Ctrl+click: add the cell, or drop it if it was selected already
+    pub fn toggle_selected(&mut self, row: i32, col: i32) {
+        if self.is_selected(row as usize, col as usize) {
+            self.selected_cells.retain(|&cell| cell != (row, col));
+            self.selected_square = self.selected_cells.last().copied();
+        } else {
+            self.add_to_selection(row, col);
+        }
+    }
+
+    pub fn is_selected(&self, row: usize, col: usize) -> bool {
+        self.selected_cells.contains(&(row as i32, col as i32))
+    }
+
+    // Selected cells the player can change, i.e. everything but the givens
+    pub fn editable_cells(&self) -> Vec<(usize, usize)> {
+        self.selected_cells
+            .iter()
+            .map(|&(row, col)| (row as usize, col as usize))
+            .filter(|&(row, col)| self.initial_board[row][col].is_none())
+            .collect()
+    }
+
+    // Move the selection by the given number of rows and columns, skipping
+    // cells outside the sub-grids. Selects the top-left cell if nothing is
+    // selected yet.
+    pub fn move_selection(&mut self, rows: i32, cols: i32) {
+        if let Some((row, col)) = self.next_cell(rows, cols) {
+            self.select(row, col);
+        }
+    }
+
+    // Like move_selection, but keeps the cells selected so far
+    pub fn extend_selection(&mut self, rows: i32, cols: i32) {
+        if let Some((row, col)) = self.next_cell(rows, cols) {
+            self.add_to_selection(row, col);
+        }
+    }
+
+    fn next_cell(&self, rows: i32, cols: i32) -> Option<(i32, i32)> {
+        if !self.board_initialized {
+            return None;
+        }
+        let Some((row, col)) = self.selected_square else {
+            return Some((0, 0));
+        };
+
+        let size = self.regions.size() as i32;
+        let (mut next_row, mut next_col) = (row + rows, col + cols);
+        while (0..size).contains(&next_row) && (0..size).contains(&next_col) {
+            if self.regions.is_active(next_row as usize, next_col as usize) {
+                return Some((next_row, next_col));
+            }
+            next_row += rows;
+            next_col += cols;
+        }
+        None
+    }
+
+    pub fn digit_source(&self, row: usize, col: usize) -> Option<DigitSource> {
+        if self.initial_board[row][col].is_some() {
+            Some(DigitSource::Given)
+        } else {
+            self.board[row][col].map(|_| self.entry_sources[row][col])
+        }
+    }
+
+    // Start a puzzle with the chosen difficulty, layout and variants. With a
+    // puzzle queue and none ready, the board is cleared and tick starts the
+    // puzzle once the worker has it.
+    pub fn new_puzzle(&mut self) -> Vec<GameEvent> {
+        self.shelve_library_puzzle();
+        self.library_key = None;
+        if self.puzzle_queue.is_none() {
+            self.generate_new_board();
+            self.start_playing();
+            return self.emit(vec![GameEvent::NewPuzzle]);
+        }
+        self.daily = None;
+        self.wait_for_queue()
+    }
+
+    // Clear the board until the worker has the puzzle, see start_queued_puzzle
+    fn wait_for_queue(&mut self) -> Vec<GameEvent> {
+        self.clear_selection();
+        self.board_initialized = false;
+        self.puzzle_solved = false;
+        self.completion = None;
+        self.loading = Some(Duration::ZERO);
+        self.start_queued_puzzle()
+    }
+
+    // Start a ready puzzle of the chosen kind, or the daily puzzle being
+    // loaded, if the queue has it. Asks for the chosen kind either way, so a
+    // change of difficulty while waiting is followed.
+    fn start_queued_puzzle(&mut self) -> Vec<GameEvent> {
+        let kind = self.next_kind();
+        let Some(queue) = &self.puzzle_queue else {
+            return Vec::new();
+        };
+        let puzzle = match self.daily {
+            Some(date) => {
+                queue.want_seeded(daily_kind(date), daily::seed(date));
+                queue.take_seeded(daily_kind(date), daily::seed(date))
+            }
+            None => {
+                queue.want(kind);
+                queue.take(kind)
+            }
+        };
+        let Some(puzzle) = puzzle else {
+            return Vec::new();
+        };
+        self.use_puzzle(puzzle);
+        self.start_playing();
+        self.emit(vec![GameEvent::NewPuzzle])
+    }
+
+    // Start a classic puzzle from the library, carrying on where the player
+    // left it if they played it before
+    pub fn open_library_puzzle(&mut self, index: usize) -> Result<Vec<GameEvent>, String> {
+        let puzzle = self.library.puzzles.get(index).ok_or(format!("no puzzle {} in the library", index))?;
+        let key = puzzle.key();
+        let (givens, difficulty) = (puzzle.givens.clone(), puzzle.difficulty);
+        let saved = self.library.progress.puzzles.get(&key).and_then(|progress| progress.game.clone());
+        let solution = solver::Solver::new(regions::Regions::classic())
+            .solve(&givens)
+            .ok_or(format!("{} has no solution", key))?;
+
+        self.shelve_library_puzzle();
+        self.library_open = false;
+        self.daily = None;
+        if let Some(saved) = saved {
+            saved.restore(self)?;
+            self.library_key = Some(key);
+            return Ok(self.emit(vec![GameEvent::NewPuzzle]));
+        }
+
+        let size = givens.len();
+        self.difficulty = difficulty;
+        self.layout = board_generator::BoardLayout::Classic;
+        self.set_rules(regions::Regions::classic(), constraints::Constraints::default());
+        self.board = givens.clone();
+        self.initial_board = givens;
+        self.solved_board = solution;
+        self.entry_sources = vec![vec![DigitSource::Player; size]; size];
+        self.cell_colors = vec![vec![None; size]; size];
+        self.zoom = 100;
+        self.scroll = (0, 0);
+        self.clear_history();
+        self.start_playing();
+        self.library_key = Some(key);
+        Ok(self.emit(vec![GameEvent::NewPuzzle]))
+    }
+
+    // Start the daily puzzle of a date. It is the same puzzle on every
+    // machine, see daily::seed. Like new_puzzle, it is made on the spot or
+    // the board waits for the worker.
+    pub fn start_daily(&mut self, date: NaiveDate) -> Vec<GameEvent> {
+        self.shelve_library_puzzle();
+        self.library_key = None;
+        self.calendar_open = false;
+        self.difficulty = daily::difficulty(date);
+        self.layout = board_generator::BoardLayout::Classic;
+        self.daily = Some(date);
+        if self.puzzle_queue.is_some() {
+            return self.wait_for_queue();
+        }
+        let puzzle = GeneratedPuzzle::generate_seeded(daily_kind(date), daily::seed(date), &AtomicBool::new(false));
+        self.use_puzzle(puzzle.unwrap());
+        self.start_playing();
+        self.emit(vec![GameEvent::NewPuzzle])
+    }
+
+    // Keep the board of a library puzzle the player moves away from, so the
+    // browser can resume it
+    pub fn shelve_library_puzzle(&mut self) {
+        let Some(key) = self.library_key.clone() else {
+            return;
+        };
+        if let Some(saved) = SavedGame::from_state(self) {
+            self.library.progress.shelve(&key, saved);
+        }
+    }
+
+    // Fresh marks, clock and counters for the board just set up
+    fn start_playing(&mut self) {
+        self.loading = None;
+        self.clear_selection();
+        self.board_initialized = true;
+        self.puzzle_solved = false;
+        self.invalid_positions = Vec::new();
+        self.corner_marks = vec![vec![None; self.board.len()]; self.board.len()];
+        self.center_marks = vec![vec![None; self.board.len()]; self.board.len()];
+        self.elapsed = Duration::ZERO;
+        self.completion = None;
+        self.mistakes = 0;
+        self.hints_used = 0;
+    }
+
+    // Leave the completion screen for the start screen, to pick the settings
+    // of the next puzzle
+    pub fn close_completion(&mut self) {
+        let size = self.board.len();
+        self.board = vec![vec![None; size]; size];
+        self.initial_board = vec![vec![None; size]; size];
+        self.solved_board = vec![vec![None; size]; size];
+        self.cell_colors = vec![vec![None; size]; size];
+        self.corner_marks = vec![vec![None; size]; size];
+        self.center_marks = vec![vec![None; size]; size];
+        self.puzzle_solved = false;
+        self.completion = None;
+        self.library_key = None;
+        self.daily = None;
+    }
+
+    // The result of the finished puzzle as text to send to someone, with the
+    // puzzle itself for classic boards so they can try it too
+    pub fn share(&mut self) -> Option<String> {
+        let completion = self.completion.as_mut()?;
+        completion.shared = true;
+        let mut text = format!("Sudoku: {}", completion.summary()[..3].join(", "));
+        if self.regions.is_classic() {
+            let givens: String = self.initial_board
+                .iter()
+                .flatten()
+                .map(|cell| cell.map_or('.', |digit| (b'0' + digit as u8) as char))
+                .collect();
+            text += &format!("\n{}", givens);
+        }
+        Some(text)
+    }
+
+    // The completion wave is running, unless motion is turned off, or a new
+    // puzzle is on its way
+    pub fn is_animating(&self) -> bool {
+        let completing = self.completion.as_ref().is_some_and(|completion| completion.age < COMPLETION_ANIMATION);
+        self.loading.is_some() || (self.config.animations && completing)
+    }
+
+    // How strongly a cell lights up in the completion animation, from 0 to 1.
+    // A wave runs diagonally from the top-left corner to the bottom-right one.
+    pub fn completion_glow(&self, row: usize, col: usize) -> f32 {
+        let Some(completion) = self.completion.as_ref().filter(|_| self.is_animating()) else {
+            return 0.0;
+        };
+        let progress = completion.age.as_secs_f32() / COMPLETION_ANIMATION.as_secs_f32();
+        let position = (row + col) as f32 / (2 * self.regions.size() - 2) as f32;
+        animation::wave(progress, position, 0.6)
+    }
+
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.recent_events)
+    }
+
+    // Every method that returns events passes them through here, so the front
+    // end sees all of them in take_events
+    fn emit(&mut self, events: Vec<GameEvent>) -> Vec<GameEvent> {
+        self.recent_events.extend(&events);
+        events
+    }
+
+    // Advance the game clock by the time since the last call. Called once per
+    // pass of the front end's loop, which is also where timed events come from.
+    pub fn tick(&mut self, dt: Duration) -> Vec<GameEvent> {
+        if self.board_initialized && !self.puzzle_solved {
+            self.elapsed += dt;
+        }
+        if let Some(completion) = &mut self.completion {
+            completion.age += dt;
+        }
+        match (&mut self.loading, &self.puzzle_queue) {
+            (Some(waited), _) => {
+                *waited += dt;
+                return self.start_queued_puzzle();
+            }
+            // Have puzzles ready for whatever the player picks next
+            (None, Some(queue)) => queue.want(self.next_kind()),
+            (None, None) => {}
+        }
+        Vec::new()
+    }
+
+    // Put a digit into every selected cell the player can change
+    pub fn place(&mut self, digit: i32) -> Vec<GameEvent> {
+        self.edit_selection(|game_state, cells, events| {
+            for &(row, col) in cells {
+                game_state.corner_marks[row][col] = None;
+                game_state.center_marks[row][col] = None;
+                game_state.board[row][col] = Some(digit);
+                game_state.entry_sources[row][col] = DigitSource::Player;
+                events.push(GameEvent::DigitPlaced { row, col, digit });
+            }
+        })
+    }
+
+    // Clear the digits of the selected cells, or the pencil marks of those
+    // without a digit
+    pub fn clear(&mut self) -> Vec<GameEvent> {
+        self.edit_selection(|game_state, cells, events| {
+            for &(row, col) in cells {
+                if game_state.board[row][col].is_some() {
+                    game_state.board[row][col] = None;
+                    game_state.invalid_positions.retain(|&(r, c, _)| (r as usize, c as usize) != (row, col));
+                    events.push(GameEvent::CellCleared { row, col });
+                } else if game_state.corner_marks[row][col].is_some() || game_state.center_marks[row][col].is_some() {
+                    game_state.corner_marks[row][col] = None;
+                    game_state.center_marks[row][col] = None;
+                    events.push(GameEvent::MarksChanged { row, col });
+                }
+            }
+        })
+    }
+
+    // Toggle a pencil mark in the empty selected cells. The mark is removed if
+    // every one of them has it already, otherwise it is added to all.
+    pub fn toggle_candidate(&mut self, digit: i32, kind: MarkKind) -> Vec<GameEvent> {
+        self.edit_selection(|game_state, cells, events| {
+            let cells: Vec<(usize, usize)> = cells
+                .iter()
+                .copied()
+                .filter(|&(row, col)| game_state.board[row][col].is_none())
+                .collect();
+            let marks = match kind {
+                MarkKind::Corner => &mut game_state.corner_marks,
+                MarkKind::Center => &mut game_state.center_marks,
+            };
+            let remove = cells
+                .iter()
+                .all(|&(row, col)| marks[row][col].as_ref().is_some_and(|marks| marks.contains(&digit)));
+
+            for (row, col) in cells {
+                let marks = marks[row][col].get_or_insert_with(Vec::new);
+                if remove {
+                    marks.retain(|&mark| mark != digit);
+                } else if !marks.contains(&digit) {
+                    marks.push(digit);
+                }
+                events.push(GameEvent::MarksChanged { row, col });
+            }
+        })
+    }
+
+    // Reveal the solution of the cell the cursor is on
+    pub fn hint(&mut self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        self.edit(|game_state| {
+            if game_state.place_hint() {
+                let (row, col) = game_state.selected_square.unwrap_or_default();
+                events.push(GameEvent::HintPlaced { row: row as usize, col: col as usize });
+                game_state.update_conflicts(&[], &mut events);
+            }
+        });
+        if !events.is_empty() {
+            self.hints_used += 1;
+        }
+        self.check_completion(&mut events);
+        self.emit(events)
+    }
+
+    // Fill in every missing or wrong digit from the solution. The puzzle is
+    // not finished by the player then, see solver_filled.
+    pub fn solve(&mut self) -> Vec<GameEvent> {
+        if !self.board_initialized {
+            return Vec::new();
+        }
+        self.edit(|game_state| {
+            for (row, sources) in game_state.entry_sources.iter_mut().enumerate() {
+                for (col, source) in sources.iter_mut().enumerate() {
+                    if game_state.board[row][col] != game_state.solved_board[row][col] {
+                        *source = DigitSource::Solver;
+                    }
+                }
+            }
+            game_state.board = game_state.solved_board.clone();
+            game_state.invalid_positions = Vec::new();
+        });
+        self.emit(vec![GameEvent::Solved])
+    }
+
+    // Run an edit of the selected cells the player can change as one undo
+    // step, then check those cells against the rules and the board for completion
+    fn edit_selection<F>(&mut self, edit: F) -> Vec<GameEvent>
+        where F: FnOnce(&mut GameState, &[(usize, usize)], &mut Vec<GameEvent>)
+    {
+        let cells = self.editable_cells();
+        if !self.board_initialized || cells.is_empty() {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        self.edit(|game_state| {
+            edit(game_state, &cells, &mut events);
+            game_state.update_conflicts(&cells, &mut events);
+        });
+        self.check_completion(&mut events);
+        self.emit(events)
+    }
+
+    // Player digits that clash with another digit on the board. Givens,
+    // hints and solver digits are never marked.
+    pub fn find_conflicts(&self) -> Vec<(i32, i32, i32)> {
+        let size = self.board.len();
+        let mut conflicts = Vec::new();
+        for row in 0..size {
+            for col in 0..size {
+                if let (Some(digit), Some(DigitSource::Player)) = (self.board[row][col], self.digit_source(row, col)) {
+                    if !self.is_valid_move(&self.board, row, col, digit) {
+                        conflicts.push((row as i32, col as i32, digit));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    // Mark the conflicts on the board after an edit, the same way a restored
+    // game marks them. A new conflict in an edited cell counts as a mistake.
+    fn update_conflicts(&mut self, edited: &[(usize, usize)], events: &mut Vec<GameEvent>) {
+        let conflicts = self.find_conflicts();
+        for &(row, col, digit) in &conflicts {
+            let (row, col) = (row as usize, col as usize);
+            if edited.contains(&(row, col)) && !self.invalid_positions.contains(&(row as i32, col as i32, digit)) {
+                self.mistakes += 1;
+                events.push(GameEvent::Conflict { row, col, digit });
+            }
+        }
+        self.invalid_positions = conflicts;
+    }
+
+    // Whether Solve filled in any digit on the board
+    pub fn solver_filled(&self) -> bool {
+        let size = self.board.len();
+        (0..size)
+            .flat_map(|row| (0..size).map(move |col| (row, col)))
+            .any(|(row, col)| self.digit_source(row, col) == Some(DigitSource::Solver))
+    }
+
+    // Once every cell is filled without breaking a rule the puzzle is over,
+    // even if the board differs from the stored solution, as it can for
+    // imported puzzles with more than one. The board can no longer be changed
+    // but stays on screen. Runs after every action that changes the board.
+    // A board Solve filled in is never the player's, it counts for no record,
+    // library progress or daily streak.
+    fn check_completion(&mut self, events: &mut Vec<GameEvent>) {
+        if !self.board_initialized || self.solver_filled() {
+            return;
+        }
+        let filled = (0..self.board.len())
+            .flat_map(|row| (0..self.board.len()).map(move |col| (row, col)))
+            .all(|(row, col)| !self.regions.is_active(row, col) || self.board[row][col].is_some());
+        if !filled || !self.solver.is_complete(&self.board) {
+            return;
+        }
+        let alternate_solution = self.board != self.solved_board;
+        if let Some(key) = &self.library_key {
+            self.library.progress.mark_solved(key, self.elapsed);
+        }
+        if let Some(date) = self.daily {
+            self.records.record_daily(date, self.elapsed, daily::today());
+        }
+        self.completion = Some(Completion {
+            time: self.elapsed,
+            mistakes: self.mistakes,
+            hints: self.hints_used,
+            difficulty: self.difficulty,
+            layout: self.layout,
+            previous_best: self.records.record(self.layout, self.difficulty, self.elapsed),
+            age: Duration::ZERO,
+            alternate_solution,
+            shared: false,
+            daily: self.daily,
+            streak: self.records.current_streak(daily::today()),
+        });
+        self.clear_selection();
+        self.board_initialized = false;
+        self.puzzle_solved = true;
+        self.invalid_positions = Vec::new();
+        self.clear_history();
+        if alternate_solution {
+            events.push(GameEvent::AlternateSolution);
+        }
+        events.push(GameEvent::Completed);
+    }
+
+    // Reveal the solution of the selected cell if it is empty or wrong.
+    // Returns true if a digit was placed.
+    pub fn place_hint(&mut self) -> bool {
+        let Some((row, col)) = self.selected_square else {
+            return false;
+        };
+        let (row, col) = (row as usize, col as usize);
+        if !self.board_initialized || self.board[row][col] == self.solved_board[row][col] {
+            return false;
+        }
+
+        self.board[row][col] = self.solved_board[row][col];
+        self.entry_sources[row][col] = DigitSource::Hint;
+        self.corner_marks[row][col] = None;
+        self.center_marks[row][col] = None;
+        self.invalid_positions.retain(|&(r, c, _)| (r as usize, c as usize) != (row, col));
+        true
     }
 
     pub fn is_valid_move(
@@ -114,53 +1244,47 @@ impl GameState {
         col: usize,
         val: i32
     ) -> bool {
-        // Check if the value is already in the row
-        if board[row][col] == None {
+        if board[row][col].is_none() {
             return true;
         }
 
-        for i in 0..9 {
-            if board[row][i] == Some(val) && i != col {
-                print!("found match in row:{}", i);
-                return false;
-            }
-        }
-        // Check if the value is already in the column
-        for i in 0..9 {
-            if board[i][col] == Some(val) && i != row {
-                print!("found match in col:{}", i);
-                return false;
-            }
-        }
-        // Check if the value is already in the 3x3 square
-        let square_row = row - (row % 3);
-        let square_col = col - (col % 3);
-        for i in square_row..square_row + 3 {
-            for j in square_col..square_col + 3 {
-                if board[i][j] == Some(val) && i != row && j != col {
-                    print!("found match in square:{},{}", i, j);
-                    return false;
-                }
-            }
-        }
-        return true;
+        // Check if the value is already in the row, column or region, or breaks a variant constraint
+        self.solver.is_valid(board, row, col, val)
     }
 }
 
 pub struct Game {
     pub screen_renderer: ScreenRenderer,
+    pub event_pump: EventPump,
     pub game_state: GameState,
     pub gui_data: GuiData,
 }
 
 impl Game {
     pub fn new() -> Game {
-        let screen_renderer = ScreenRenderer::new();
-        let game_state = GameState::new();
-        let (window_width, window_height) = screen_renderer.context.canvas.window().size();
-        let gui_data = GuiData::new(window_width, window_height);
+        let config = Config::load();
+        let SdlContext { canvas, event_pump } = SdlContext::new(&config.window);
+        let screen_renderer = ScreenRenderer::with_window(canvas);
+        let mut game_state = GameState::new();
+        game_state.themes.extend(Theme::load_dir(&config::resolve_path("assets/themes")));
+        game_state.apply_config(config);
+        game_state.records = Records::load();
+        game_state.library = Library::load();
+        game_state.puzzle_queue = Some(PuzzleQueue::start());
+        // Continue the puzzle from the last session, in this or the terminal front end
+        if let Some(saved) = SavedGame::load() {
+            if let Err(e) = saved.restore(&mut game_state) {
+                eprintln!("Ignoring saved game: {}", e);
+            }
+        }
+        let gui_data = GuiData::new(
+            screen_renderer.window_width as u32,
+            screen_renderer.window_height as u32,
+            screen_renderer.dpi_scale
+        );
         Game {
             screen_renderer,
+            event_pump,
             game_state,
             gui_data,
         }
@@ -168,37 +1292,131 @@ impl Game {
 
     pub fn start(&mut self) -> Result<(), String> {
         let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
-        let button_font = ttf_context.load_font("./assets/fonts/LibreFranklin-Medium.ttf", self.gui_data.font_size_buttons)?;
-        let numbers_font = ttf_context.load_font("./assets/fonts/LibreFranklin-Bold.ttf", self.gui_data.font_size_numbers)?;
-        let message_font = ttf_context.load_font("./assets/fonts/LibreFranklin-Bold.ttf", self.gui_data.font_size_message)?;
-        let candidates_font = ttf_context.load_font("./assets/fonts/LibreFranklin-Medium.ttf", self.gui_data.font_size_candidates)?;
-    
-        let mut fonts: Vec<&sdl2::ttf::Font> = Vec::new();
-        fonts.push(&button_font);
-        fonts.push(&numbers_font);
-        fonts.push(&message_font);
-        fonts.push(&candidates_font);
+        let font_config = fonts::usable_fonts(&ttf_context, &self.game_state.config.fonts);
+        let mut fonts = fonts::load_fonts(&ttf_context, &font_config, &self.gui_data)?;
+
+        // Text is rendered to textures once and reused on every frame
+        let texture_creator = self.screen_renderer.canvas.texture_creator();
+        let mut glyphs = GlyphCache::new(&texture_creator);
+        glyphs.preload_digits(&fonts.iter().collect::<Vec<_>>(), self.game_state.theme())?;
+        let mut frame_timer = FrameTimer::new(std::env::var_os("SUDOKU_FRAME_TIMES").is_some());
+        // Write all input to a replay script, see replay.rs
+        let mut recorder = match std::env::var_os("SUDOKU_RECORD") {
+            Some(path) => Some(Recorder::create(std::path::Path::new(&path))?),
+            None => None,
+        };
 
         // Game loop
+        self.game_state.needs_redraw = true;
+        let mut last_tick = Instant::now();
         'running: loop {
-            // Handle events
-            if !Events::process_events(
-                &mut self.game_state,
-                &mut self.screen_renderer.context.event_pump,
-                &mut self.screen_renderer.context.canvas,
-                &self.gui_data
-            ) {
+            // Handle events, sleeping until something happens while idle
+            let animating = self.game_state.is_animating() || self.screen_renderer.animations.is_running();
+            let timeout = if animating { FRAME_TIMEOUT_MS } else { IDLE_TIMEOUT_MS };
+            let input = Events::poll(&mut self.event_pump, &self.gui_data, timeout);
+            if let Some(recording) = &mut recorder {
+                if let Err(e) = recording.record(&input) {
+                    eprintln!("Stopped recording input: {}", e);
+                    recorder = None;
+                }
+            }
+            if !Events::process_input(&mut self.game_state, &self.gui_data, input) {
                 break 'running;
             }
+            let now = Instant::now();
+            self.game_state.tick(now - last_tick);
+            self.screen_renderer.animations.advance(now - last_tick);
+            last_tick = now;
+            for event in self.game_state.take_events() {
+                self.screen_renderer.animations.start(&event, &self.game_state);
+            }
+            // Also draws the frame after the last one, where the animation has ended
+            if animating {
+                self.game_state.needs_redraw = true;
+            }
+            if let Some(text) = self.game_state.clipboard.take() {
+                let clipboard = self.screen_renderer.canvas.window().subsystem().clipboard();
+                if let Err(e) = clipboard.set_clipboard_text(&text) {
+                    eprintln!("Could not copy to the clipboard: {}", e);
+                }
+            }
 
-            // Render the screen
-            self.screen_renderer.draw(&self.game_state, fonts.clone(), &self.gui_data)?;
+            // Recompute the layout and reload the fonts at their new sizes
+            // whenever the window is resized or moved to a display with a
+            // different pixel density
+            if self.screen_renderer.update_size() {
+                self.gui_data = GuiData::new(
+                    self.screen_renderer.window_width as u32,
+                    self.screen_renderer.window_height as u32,
+                    self.screen_renderer.dpi_scale
+                );
+                fonts = fonts::load_fonts(&ttf_context, &font_config, &self.gui_data)?;
+                glyphs.clear();
+                glyphs.preload_digits(&fonts.iter().collect::<Vec<_>>(), self.game_state.theme())?;
+                Events::scroll_board(&mut self.game_state, &self.gui_data, 0, 0);
+                self.game_state.needs_redraw = true;
+            }
 
-            // Set the framerate to 60fps
-            std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+            // Render the screen only if something changed. The back buffer is
+            // not preserved between presents, so every frame is drawn in full.
+            if self.game_state.needs_redraw {
+                let frame_start = Instant::now();
+                self.screen_renderer.draw(&self.game_state, fonts.iter().collect(), &self.gui_data, &mut glyphs)?;
+                frame_timer.record(frame_start.elapsed(), glyphs.len());
+                self.game_state.needs_redraw = false;
+            }
         }
 
+        if let Err(e) = SavedGame::store(&self.game_state) {
+            eprintln!("Could not save the game: {}", e);
+        }
+        if let Err(e) = self.game_state.records.save() {
+            eprintln!("Could not save the records: {}", e);
+        }
+        self.game_state.shelve_library_puzzle();
+        if let Err(e) = self.game_state.library.progress.save() {
+            eprintln!("Could not save the library progress: {}", e);
+        }
         Ok(())
     }
-    
+}
+
+// Measures how long drawing a frame takes. Set SUDOKU_FRAME_TIMES to print
+// the average every few seconds.
+struct FrameTimer {
+    enabled: bool,
+    frames: u32,
+    total: Duration,
+    last_report: Instant,
+}
+
+impl FrameTimer {
+    fn new(enabled: bool) -> Self {
+        FrameTimer {
+            enabled,
+            frames: 0,
+            total: Duration::ZERO,
+            last_report: Instant::now(),
+        }
+    }
+
+    fn record(&mut self, frame_time: Duration, cached_glyphs: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        self.frames += 1;
+        self.total += frame_time;
+        if self.last_report.elapsed() >= Duration::from_secs(5) {
+            println!(
+                "average frame time: {:.3} ms over {} frames ({} cached glyphs)",
+                self.total.as_secs_f64() * 1000.0 / f64::from(self.frames),
+                self.frames,
+                cached_glyphs
+            );
+            self.frames = 0;
+            self.total = Duration::ZERO;
+            self.last_report = Instant::now();
+        }
+    }
 }
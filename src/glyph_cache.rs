@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+use crate::theme::{Color, Theme};
+
+// Indices into the font list loaded by the game
+pub const FONT_BUTTONS: usize = 0;
+pub const FONT_NUMBERS: usize = 1;
+pub const FONT_MESSAGE: usize = 2;
+pub const FONT_CANDIDATES: usize = 3;
+// Digits entered by the player, lighter than the givens
+pub const FONT_ENTRIES: usize = 4;
+
+// Most textures kept at once. Far more than one frame draws, while strings
+// that change all the time, like the clock, can't pile up.
+const CAPACITY: usize = 512;
+
+// Rendered text textures, keyed by font, text and color. Rendering a TTF
+// surface and uploading it as a texture is by far the most expensive part of
+// a frame, so every string is only rendered the first time it is drawn. When
+// the cache is full the texture used longest ago makes room. The cache has to
+// be cleared whenever the fonts are reloaded at a new size.
+// Textures belong to the canvas they were created for, a window by default.
+pub struct GlyphCache<'a, C = WindowContext> {
+    texture_creator: &'a TextureCreator<C>,
+    // Each texture with the value of uses when it was last drawn
+    textures: HashMap<(usize, String, Color), (Texture<'a>, u64)>,
+    uses: u64,
+}
+
+impl<'a, C> GlyphCache<'a, C> {
+    pub fn new(texture_creator: &'a TextureCreator<C>) -> GlyphCache<'a, C> {
+        GlyphCache {
+            texture_creator,
+            textures: HashMap::new(),
+            uses: 0,
+        }
+    }
+
+    // Render the digits up front so the first frames of a new puzzle don't stutter
+    pub fn preload_digits(&mut self, fonts: &[&Font], theme: &Theme) -> Result<(), String> {
+        for num in 1..=9 {
+            let text = num.to_string();
+            self.get(fonts, FONT_NUMBERS, &text, theme.given_digit)?;
+            self.get(fonts, FONT_ENTRIES, &text, theme.user_digit)?;
+            self.get(fonts, FONT_CANDIDATES, &text, theme.candidate)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(
+        &mut self,
+        fonts: &[&Font],
+        font: usize,
+        text: &str,
+        color: Color
+    ) -> Result<&Texture<'a>, String> {
+        let key = (font, text.to_string(), color);
+        self.uses += 1;
+        if let Some((_, last_used)) = self.textures.get_mut(&key) {
+            *last_used = self.uses;
+        } else {
+            if self.textures.len() >= CAPACITY {
+                self.evict_oldest();
+            }
+            let surface = fonts[font]
+                .render(text)
+                .blended(color)
+                .map_err(|e| e.to_string())?;
+            let texture = self.texture_creator
+                .create_texture_from_surface(&surface)
+                .map_err(|e| e.to_string())?;
+            self.textures.insert(key.clone(), (texture, self.uses));
+        }
+        Ok(&self.textures[&key].0)
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.textures
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.textures.remove(&key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+}
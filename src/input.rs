@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+// Input as the game sees it, independent of where it came from: the SDL
+// event loop, or a replay script in tests. Mouse positions are in drawable
+// pixels.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InputEvent {
+    // The left mouse button went down or up
+    MouseDown { x: i32, y: i32 },
+    MouseUp { x: i32, y: i32 },
+    // The mouse moved by (dx, dy) with the given buttons held
+    MouseMove { x: i32, y: i32, dx: i32, dy: i32, left: bool, right: bool },
+    MouseLeave,
+    Wheel { x: i32, y: i32 },
+    KeyDown(KeyChord),
+    KeyUp(KeyChord),
+    FocusLost,
+    // The window was closed
+    Quit,
+    // Anything else, which only needs a redraw
+    Other,
+}
+
+// Everything the keyboard can do. Keys are mapped to these through a KeyMap,
+// so the game logic never looks at raw key codes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InputAction {
+    // Place a digit, or toggle it as a corner mark while candidate mode is on
+    PlaceDigit(i32),
+    // Toggle a pencil mark regardless of candidate mode
+    CornerMark(i32),
+    CenterMark(i32),
+    Clear,
+    // Move the selection by (rows, columns)
+    Move(i32, i32),
+    // Add the next cell in that direction to the selection
+    ExtendSelection(i32, i32),
+    Undo,
+    Redo,
+    Hint,
+    ToggleCandidateMode,
+    // Switch digit keys and clicks between filling and painting cells
+    ToggleColorMode,
+    ClearColors,
+    ZoomIn,
+    ZoomOut,
+    NextTheme,
+    NewPuzzle,
+    // Show or hide the puzzle library
+    OpenLibrary,
+    // Show or hide the calendar of daily puzzles
+    OpenCalendar,
+    // Asks for confirmation before quitting
+    Quit,
+}
+
+impl InputAction {
+    // Every action with its name in the config file
+    pub fn all() -> Vec<(InputAction, String)> {
+        let mut actions = Vec::new();
+        for num in 1..=9 {
+            actions.push((InputAction::PlaceDigit(num), format!("place_{}", num)));
+        }
+        for num in 1..=9 {
+            actions.push((InputAction::CornerMark(num), format!("corner_{}", num)));
+        }
+        for num in 1..=9 {
+            actions.push((InputAction::CenterMark(num), format!("center_{}", num)));
+        }
+        let named = [
+            (InputAction::Clear, "clear"),
+            (InputAction::Move(-1, 0), "move_up"),
+            (InputAction::Move(1, 0), "move_down"),
+            (InputAction::Move(0, -1), "move_left"),
+            (InputAction::Move(0, 1), "move_right"),
+            (InputAction::ExtendSelection(-1, 0), "extend_up"),
+            (InputAction::ExtendSelection(1, 0), "extend_down"),
+            (InputAction::ExtendSelection(0, -1), "extend_left"),
+            (InputAction::ExtendSelection(0, 1), "extend_right"),
+            (InputAction::Undo, "undo"),
+            (InputAction::Redo, "redo"),
+            (InputAction::Hint, "hint"),
+            (InputAction::ToggleCandidateMode, "candidate_mode"),
+            (InputAction::ToggleColorMode, "color_mode"),
+            (InputAction::ClearColors, "clear_colors"),
+            (InputAction::ZoomIn, "zoom_in"),
+            (InputAction::ZoomOut, "zoom_out"),
+            (InputAction::NextTheme, "next_theme"),
+            (InputAction::NewPuzzle, "new_puzzle"),
+            (InputAction::OpenLibrary, "library"),
+            (InputAction::OpenCalendar, "daily"),
+            (InputAction::Quit, "quit"),
+        ];
+        actions.extend(named.iter().map(|&(action, name)| (action, name.to_string())));
+        actions
+    }
+
+    pub fn from_name(name: &str) -> Option<InputAction> {
+        Self::all()
+            .into_iter()
+            .find(|(_, action_name)| action_name == name)
+            .map(|(action, _)| action)
+    }
+
+    // Keys bound to the action unless the config file says otherwise
+    pub fn default_keys(&self) -> Vec<String> {
+        match *self {
+            InputAction::PlaceDigit(num) => vec![num.to_string(), format!("Keypad {}", num)],
+            InputAction::CornerMark(num) => vec![format!("Shift+{}", num), format!("Shift+Keypad {}", num)],
+            InputAction::CenterMark(num) => vec![format!("Ctrl+{}", num), format!("Ctrl+Keypad {}", num)],
+            InputAction::Clear => vec!["Backspace".to_string(), "Delete".to_string()],
+            InputAction::Move(-1, 0) => vec!["Up".to_string()],
+            InputAction::Move(1, 0) => vec!["Down".to_string()],
+            InputAction::Move(0, -1) => vec!["Left".to_string()],
+            InputAction::Move(_, _) => vec!["Right".to_string()],
+            InputAction::ExtendSelection(-1, 0) => vec!["Shift+Up".to_string()],
+            InputAction::ExtendSelection(1, 0) => vec!["Shift+Down".to_string()],
+            InputAction::ExtendSelection(0, -1) => vec!["Shift+Left".to_string()],
+            InputAction::ExtendSelection(_, _) => vec!["Shift+Right".to_string()],
+            InputAction::Undo => vec!["Ctrl+Z".to_string()],
+            InputAction::Redo => vec!["Ctrl+Y".to_string(), "Ctrl+Shift+Z".to_string()],
+            InputAction::Hint => vec!["H".to_string()],
+            InputAction::ToggleCandidateMode => vec!["C".to_string()],
+            InputAction::ToggleColorMode => vec!["K".to_string()],
+            InputAction::ClearColors => vec!["Shift+K".to_string()],
+            InputAction::ZoomIn => vec!["=".to_string(), "+".to_string(), "Keypad +".to_string()],
+            InputAction::ZoomOut => vec!["-".to_string(), "Keypad -".to_string()],
+            InputAction::NextTheme => vec!["T".to_string()],
+            InputAction::NewPuzzle => vec!["N".to_string()],
+            InputAction::OpenLibrary => vec!["L".to_string()],
+            InputAction::OpenCalendar => vec!["D".to_string()],
+            InputAction::Quit => vec!["Escape".to_string()],
+        }
+    }
+}
+
+// The keys that can be bound, named the way SDL names them. The front ends
+// translate their own key codes by name, so nothing here needs SDL.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Key {
+    Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+    Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9,
+    KpPlus, KpMinus, KpMultiply, KpDivide, KpPeriod, KpEnter,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Equals, Plus, Minus, Comma, Period, Slash, Backslash, Semicolon, Quote, Backquote,
+    LeftBracket, RightBracket,
+    Up, Down, Left, Right, Home, End, PageUp, PageDown,
+    Backspace, Delete, Insert, Escape, Return, Space, Tab,
+    LCtrl, RCtrl, LShift, RShift, LAlt, RAlt, LGui, RGui,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+}
+
+impl Key {
+    // Ignores case, e.g. "keypad 1" is Kp1
+    pub fn from_name(name: &str) -> Option<Key> {
+        KEY_NAMES
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|&(_, key)| key)
+    }
+
+    pub fn name(self) -> &'static str {
+        KEY_NAMES.iter().find(|&&(_, key)| key == self).map_or("", |&(name, _)| name)
+    }
+}
+
+// A key together with the modifiers held down, written like "Ctrl+Shift+Z"
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+    pub key: Key,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyChord {
+    pub fn new(key: Key) -> KeyChord {
+        KeyChord { key, ctrl: false, shift: false, alt: false }
+    }
+
+    // Key names are the ones SDL uses, e.g. "Z", "Keypad 1" or "Backspace"
+    pub fn parse(spec: &str) -> Result<KeyChord, String> {
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        let mut name = spec.trim();
+        // Peel off modifiers until the rest is the key name, which may itself
+        // contain a "+" as in "Keypad +"
+        while let Some((modifier, rest)) = name.split_once('+') {
+            match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                _ => break,
+            }
+            name = rest.trim();
+        }
+
+        let key = Key::from_name(name).ok_or(format!("unknown key `{}` in `{}`", name, spec))?;
+        Ok(KeyChord { key, ctrl, shift, alt })
+    }
+}
+
+// Written the way parse reads it, e.g. "Ctrl+Shift+Z"
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (held, modifier) in [(self.ctrl, "Ctrl+"), (self.shift, "Shift+"), (self.alt, "Alt+")] {
+            if held {
+                f.write_str(modifier)?;
+            }
+        }
+        f.write_str(self.key.name())
+    }
+}
+
+// SDL's name for every key, the first one of a key is the one it is written as
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("0", Key::Num0), ("1", Key::Num1), ("2", Key::Num2), ("3", Key::Num3),
+    ("4", Key::Num4), ("5", Key::Num5), ("6", Key::Num6), ("7", Key::Num7),
+    ("8", Key::Num8), ("9", Key::Num9),
+    ("Keypad 0", Key::Kp0), ("Keypad 1", Key::Kp1), ("Keypad 2", Key::Kp2),
+    ("Keypad 3", Key::Kp3), ("Keypad 4", Key::Kp4), ("Keypad 5", Key::Kp5),
+    ("Keypad 6", Key::Kp6), ("Keypad 7", Key::Kp7), ("Keypad 8", Key::Kp8),
+    ("Keypad 9", Key::Kp9), ("Keypad +", Key::KpPlus), ("Keypad -", Key::KpMinus),
+    ("Keypad *", Key::KpMultiply), ("Keypad /", Key::KpDivide), ("Keypad .", Key::KpPeriod),
+    ("Keypad Enter", Key::KpEnter),
+    ("A", Key::A), ("B", Key::B), ("C", Key::C), ("D", Key::D), ("E", Key::E),
+    ("F", Key::F), ("G", Key::G), ("H", Key::H), ("I", Key::I), ("J", Key::J),
+    ("K", Key::K), ("L", Key::L), ("M", Key::M), ("N", Key::N), ("O", Key::O),
+    ("P", Key::P), ("Q", Key::Q), ("R", Key::R), ("S", Key::S), ("T", Key::T),
+    ("U", Key::U), ("V", Key::V), ("W", Key::W), ("X", Key::X), ("Y", Key::Y),
+    ("Z", Key::Z),
+    ("=", Key::Equals), ("+", Key::Plus), ("-", Key::Minus), (",", Key::Comma), (".", Key::Period),
+    ("/", Key::Slash), ("\\", Key::Backslash), (";", Key::Semicolon), ("'", Key::Quote),
+    ("`", Key::Backquote), ("[", Key::LeftBracket), ("]", Key::RightBracket),
+    ("Up", Key::Up), ("Down", Key::Down), ("Left", Key::Left), ("Right", Key::Right),
+    ("Home", Key::Home), ("End", Key::End), ("PageUp", Key::PageUp), ("PageDown", Key::PageDown),
+    // Also accepted, but never written
+    ("Page Up", Key::PageUp), ("Page Down", Key::PageDown),
+    ("Backspace", Key::Backspace), ("Delete", Key::Delete), ("Insert", Key::Insert),
+    ("Escape", Key::Escape), ("Return", Key::Return), ("Space", Key::Space), ("Tab", Key::Tab),
+    ("Left Ctrl", Key::LCtrl), ("Right Ctrl", Key::RCtrl), ("Left Shift", Key::LShift),
+    ("Right Shift", Key::RShift), ("Left Alt", Key::LAlt), ("Right Alt", Key::RAlt),
+    ("Left GUI", Key::LGui), ("Right GUI", Key::RGui),
+    ("F1", Key::F1), ("F2", Key::F2), ("F3", Key::F3), ("F4", Key::F4),
+    ("F5", Key::F5), ("F6", Key::F6), ("F7", Key::F7), ("F8", Key::F8),
+    ("F9", Key::F9), ("F10", Key::F10), ("F11", Key::F11), ("F12", Key::F12),
+];
+
+// Maps key chords to actions
+#[derive(Clone, Debug, Default)]
+pub struct KeyMap {
+    bindings: HashMap<KeyChord, InputAction>,
+}
+
+impl KeyMap {
+    // The default bindings, with every action named in overrides rebound to
+    // the given keys instead. Bad entries are reported and skipped.
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>) -> KeyMap {
+        for name in overrides.keys() {
+            if InputAction::from_name(name).is_none() {
+                eprintln!("Ignoring key binding for unknown action `{}`", name);
+            }
+        }
+
+        let mut key_map = KeyMap::default();
+        for (action, name) in InputAction::all() {
+            let keys = overrides.get(&name).cloned().unwrap_or_else(|| action.default_keys());
+            for key in keys {
+                match KeyChord::parse(&key) {
+                    Ok(chord) => key_map.bind(chord, action),
+                    Err(e) => eprintln!("Ignoring key binding for `{}`: {}", name, e),
+                }
+            }
+        }
+        key_map
+    }
+
+    pub fn bind(&mut self, chord: KeyChord, action: InputAction) {
+        self.bindings.insert(chord, action);
+    }
+
+    // Shift is ignored if nothing is bound with it, so keys like "+" that
+    // need shift on some layouts still work
+    pub fn action(&self, chord: KeyChord) -> Option<InputAction> {
+        self.bindings
+            .get(&chord)
+            .or_else(|| self.bindings.get(&KeyChord { shift: false, ..chord }))
+            .copied()
+    }
+}
@@ -1,5 +1,21 @@
+pub mod animation;
 pub mod board_generator;
+pub mod config;
+pub mod constraints;
+pub mod daily;
 pub mod events;
+pub mod fonts;
 pub mod game;
+pub mod glyph_cache;
+pub mod input;
+pub mod library;
+pub mod puzzle_queue;
+pub mod regions;
+pub mod replay;
+pub mod save;
 pub mod screen_renderer;
 pub mod sdl_context;
+pub mod solver;
+pub mod theme;
+pub mod tui;
+pub mod widgets;
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::board_generator::BoardDifficulty;
+use crate::config;
+use crate::save::SavedGame;
+
+// Collections of classic puzzles to play instead of generated ones. A
+// collection is an .sdm file with one puzzle per line, 81 digits with 0 for
+// an empty cell. The bundled collections are in assets/puzzles, imported ones
+// are copied to the puzzles directory next to the save file. How far the
+// player got with each puzzle is kept in library.toml.
+
+pub struct LibraryPuzzle {
+    // Name of the file the puzzle came from, without the extension
+    pub collection: String,
+    // Line of the puzzle in its file, counting from 1
+    pub number: usize,
+    pub difficulty: BoardDifficulty,
+    pub givens: Vec<Vec<Option<i32>>>,
+    // Whether the collection was imported by the player
+    pub imported: bool,
+}
+
+impl LibraryPuzzle {
+    // Identifies the puzzle in library.toml and the save file. Imported
+    // collections get their own keys, so they never share progress with a
+    // bundled one.
+    pub fn key(&self) -> String {
+        if self.imported {
+            format!("imported/{}/{}", self.collection, self.number)
+        } else {
+            format!("{}/{}", self.collection, self.number)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PuzzleStatus {
+    #[default]
+    Unplayed,
+    InProgress,
+    Solved,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct PuzzleProgress {
+    pub status: PuzzleStatus,
+    // Fastest solve in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_time: Option<u64>,
+    // The board as the player left it, to carry on from there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<SavedGame>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct LibraryProgress {
+    // Keyed like "easy/3" or "imported/mine/3", puzzles never opened are left out
+    pub puzzles: BTreeMap<String, PuzzleProgress>,
+}
+
+impl LibraryProgress {
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "sudoku").map(|dirs| dirs.data_dir().join("library.toml"))
+    }
+
+    // Read the progress, starting over if the file is missing or broken
+    pub fn load() -> LibraryProgress {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return LibraryProgress::default();
+        };
+        Self::from_file(&path).unwrap_or_else(|e| {
+            eprintln!("Ignoring library progress: {}", e);
+            LibraryProgress::default()
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<LibraryProgress, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no data directory on this platform")?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn status(&self, key: &str) -> PuzzleStatus {
+        self.puzzles.get(key).map_or(PuzzleStatus::Unplayed, |progress| progress.status)
+    }
+
+    // Keep the board of a puzzle the player is leaving. A solved puzzle
+    // played again stays solved.
+    pub fn shelve(&mut self, key: &str, game: SavedGame) {
+        let progress = self.puzzles.entry(key.to_string()).or_default();
+        if progress.status != PuzzleStatus::Solved {
+            progress.status = PuzzleStatus::InProgress;
+        }
+        progress.game = Some(game);
+    }
+
+    pub fn mark_solved(&mut self, key: &str, time: Duration) {
+        let progress = self.puzzles.entry(key.to_string()).or_default();
+        progress.status = PuzzleStatus::Solved;
+        progress.best_time = Some(progress.best_time.map_or(time.as_secs(), |best| best.min(time.as_secs())));
+        progress.game = None;
+    }
+}
+
+#[derive(Default)]
+pub struct Library {
+    pub puzzles: Vec<LibraryPuzzle>,
+    pub progress: LibraryProgress,
+}
+
+impl Library {
+    pub fn bundled_dir() -> PathBuf {
+        config::resolve_path("assets/puzzles")
+    }
+
+    // Where imported collections are kept
+    pub fn import_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "sudoku").map(|dirs| dirs.data_dir().join("puzzles"))
+    }
+
+    // The bundled and imported collections, with the player's progress
+    pub fn load() -> Library {
+        let mut puzzles = Self::load_dir(&Self::bundled_dir());
+        if let Some(dir) = Self::import_dir() {
+            puzzles.extend(Self::load_dir(&dir).into_iter().map(|puzzle| LibraryPuzzle { imported: true, ..puzzle }));
+        }
+        Library { puzzles, progress: LibraryProgress::load() }
+    }
+
+    // Every .sdm file in the directory, in the order of their names. Broken
+    // files are reported and skipped.
+    pub fn load_dir(dir: &Path) -> Vec<LibraryPuzzle> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "sdm"))
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .flat_map(|path| {
+                Self::from_file(path)
+                    .map_err(|e| eprintln!("Skipping collection {}", e))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    pub fn from_file(path: &Path) -> Result<Vec<LibraryPuzzle>, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        Self::parse(&name, &text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // A collection named after a difficulty holds puzzles of that
+    // difficulty, the puzzles of any other are graded by their givens
+    pub fn parse(name: &str, text: &str) -> Result<Vec<LibraryPuzzle>, String> {
+        let named = match name.to_lowercase().as_str() {
+            "beginner" => Some(BoardDifficulty::Beginner),
+            "easy" => Some(BoardDifficulty::Easy),
+            "medium" => Some(BoardDifficulty::Medium),
+            "hard" => Some(BoardDifficulty::Hard),
+            "expert" => Some(BoardDifficulty::Expert),
+            _ => None,
+        };
+
+        let mut puzzles = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let cells = line
+                .chars()
+                .map(|cell| match cell {
+                    '0' | '.' => Ok(None),
+                    '1'..='9' => Ok(cell.to_digit(10).map(|digit| digit as i32)),
+                    _ => Err(format!("line {}: bad cell `{}`", index + 1, cell)),
+                })
+                .collect::<Result<Vec<Option<i32>>, String>>()?;
+            if cells.len() != 81 {
+                return Err(format!("line {}: expected 81 cells, got {}", index + 1, cells.len()));
+            }
+            let givens = cells.iter().filter(|cell| cell.is_some()).count();
+            puzzles.push(LibraryPuzzle {
+                collection: name.to_string(),
+                number: index + 1,
+                difficulty: named.unwrap_or_else(|| grade(givens)),
+                givens: cells.chunks(9).map(|row| row.to_vec()).collect(),
+                imported: false,
+            });
+        }
+        Ok(puzzles)
+    }
+
+    // Copy a collection into the import directory, after checking it can be
+    // read. Returns how many puzzles it holds.
+    pub fn import(path: &Path) -> Result<usize, String> {
+        let dir = Self::import_dir().ok_or("no data directory on this platform")?;
+        Self::import_to(path, &dir)
+    }
+
+    // A collection named like a bundled or an already imported one is
+    // refused rather than replacing it
+    pub fn import_to(path: &Path, dir: &Path) -> Result<usize, String> {
+        let puzzles = Self::from_file(path)?;
+        let name = path.file_name().ok_or(format!("{}: not a file", path.display()))?;
+        let taken = [Self::bundled_dir(), dir.to_path_buf()]
+            .iter()
+            .any(|existing| has_collection(existing, &name.to_string_lossy()));
+        if taken {
+            return Err(format!("{}: a collection of that name is already in the library", path.display()));
+        }
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        fs::copy(path, dir.join(name)).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(puzzles.len())
+    }
+
+    // Indices of the puzzles of a difficulty, or of all puzzles
+    pub fn filtered(&self, difficulty: Option<BoardDifficulty>) -> Vec<usize> {
+        (0..self.puzzles.len())
+            .filter(|&index| difficulty.is_none_or(|difficulty| self.puzzles[index].difficulty == difficulty))
+            .collect()
+    }
+}
+
+// Whether the directory has a file of that name, ignoring case
+fn has_collection(dir: &Path, name: &str) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+}
+
+// Guess the difficulty from the number of givens, in line with what the
+// generator leaves on the board
+fn grade(givens: usize) -> BoardDifficulty {
+    match givens {
+        46.. => BoardDifficulty::Beginner,
+        32..=45 => BoardDifficulty::Easy,
+        27..=31 => BoardDifficulty::Medium,
+        25..=26 => BoardDifficulty::Hard,
+        _ => BoardDifficulty::Expert,
+    }
+}
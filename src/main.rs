@@ -1,6 +1,19 @@
+use std::path::Path;
+
 use sudoku::game::Game;
+use sudoku::library::Library;
 
 fn main() -> Result<(), String> {
+    // `sudoku --import FILE.sdm` adds a collection to the puzzle library
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [flag, path] = &args[..] {
+        if flag == "--import" {
+            let count = Library::import(Path::new(path))?;
+            println!("Imported {} puzzles from {}", count, path);
+            return Ok(());
+        }
+    }
+
     let mut game = Game::new();
     game.start()?;
     
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::board_generator::{BoardDifficulty, BoardGenerator, BoardLayout};
+use crate::constraints::Constraints;
+use crate::regions::Regions;
+
+// Puzzles generated ahead of time on a worker thread, so starting one never
+// holds up the front end. The worker keeps a few puzzles ready for the
+// difficulty, layout and variants the player picked, then for the
+// difficulties either side of it. When the pick changes, the puzzles kept for
+// anything else are dropped, as is a puzzle still being made for it. A puzzle
+// from a seed, like the daily one, is made on request before anything else.
+
+// Ready puzzles kept per kind
+pub const QUEUE_SIZE: usize = 2;
+
+const DIFFICULTIES: [BoardDifficulty; 5] = [
+    BoardDifficulty::Beginner,
+    BoardDifficulty::Easy,
+    BoardDifficulty::Medium,
+    BoardDifficulty::Hard,
+    BoardDifficulty::Expert,
+];
+
+// What the generator is asked for
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PuzzleKind {
+    pub difficulty: BoardDifficulty,
+    pub layout: BoardLayout,
+    pub constraints: Constraints,
+}
+
+impl PuzzleKind {
+    // This kind, then the same layout and variants one difficulty easier
+    // and one harder
+    fn with_neighbours(self) -> Vec<PuzzleKind> {
+        let index = DIFFICULTIES.iter().position(|&difficulty| difficulty == self.difficulty).unwrap_or_default();
+        let neighbours = [index.checked_sub(1), Some(index + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|index| DIFFICULTIES.get(index))
+            .map(|&difficulty| PuzzleKind { difficulty, ..self });
+        std::iter::once(self).chain(neighbours).collect()
+    }
+}
+
+// A new puzzle with everything needed to play it
+pub struct GeneratedPuzzle {
+    pub board: Vec<Vec<Option<i32>>>,
+    pub solution: Vec<Vec<Option<i32>>>,
+    pub regions: Regions,
+    pub constraints: Constraints,
+}
+
+impl GeneratedPuzzle {
+    // The board a generator just made, with its solution and regions
+    pub fn new(board: Vec<Vec<Option<i32>>>, board_generator: &BoardGenerator) -> GeneratedPuzzle {
+        GeneratedPuzzle {
+            board,
+            solution: board_generator.get_solved_board().unwrap(),
+            regions: board_generator.get_regions(),
+            constraints: board_generator.get_constraints(),
+        }
+    }
+
+    // None if cancel was set before the puzzle was done
+    pub fn generate(kind: PuzzleKind, cancel: &AtomicBool) -> Option<GeneratedPuzzle> {
+        let mut board_generator = BoardGenerator::new_with_variant(kind.difficulty, kind.layout, kind.constraints);
+        let board = board_generator.generate_sudoku_cancellable(cancel)?;
+        Some(GeneratedPuzzle::new(board, &board_generator))
+    }
+
+    // The same seed always gives the same puzzle
+    pub fn generate_seeded(kind: PuzzleKind, seed: u64, cancel: &AtomicBool) -> Option<GeneratedPuzzle> {
+        let mut board_generator = BoardGenerator::new_with_variant(kind.difficulty, kind.layout, kind.constraints);
+        let board = board_generator.generate_sudoku_seeded_cancellable(seed, cancel)?;
+        Some(GeneratedPuzzle::new(board, &board_generator))
+    }
+}
+
+// A puzzle for one of the queues, or one from a seed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Job {
+    Queue(PuzzleKind),
+    Seeded(PuzzleKind, u64),
+}
+
+#[derive(Default)]
+struct Shared {
+    ready: Vec<(PuzzleKind, VecDeque<GeneratedPuzzle>)>,
+    // The kind the player picked, nothing is generated before there is one
+    wanted: Option<PuzzleKind>,
+    // The puzzle asked for with want_seeded, and once made the puzzle itself
+    seeded: Option<(PuzzleKind, u64)>,
+    seeded_ready: Option<GeneratedPuzzle>,
+    working_on: Option<Job>,
+    quit: bool,
+}
+
+impl Shared {
+    fn count(&self, kind: PuzzleKind) -> usize {
+        self.ready.iter().find(|(queued, _)| *queued == kind).map_or(0, |(_, puzzles)| puzzles.len())
+    }
+
+    fn queue(&mut self, kind: PuzzleKind) -> &mut VecDeque<GeneratedPuzzle> {
+        let index = match self.ready.iter().position(|(queued, _)| *queued == kind) {
+            Some(index) => index,
+            None => {
+                self.ready.push((kind, VecDeque::new()));
+                self.ready.len() - 1
+            }
+        };
+        &mut self.ready[index].1
+    }
+
+    // The seeded puzzle if it is still to be made, otherwise the first kind
+    // short of puzzles, the wanted one before its neighbours
+    fn next_job(&self) -> Option<Job> {
+        if let Some((kind, seed)) = self.seeded.filter(|_| self.seeded_ready.is_none()) {
+            return Some(Job::Seeded(kind, seed));
+        }
+        self.wanted?
+            .with_neighbours()
+            .into_iter()
+            .find(|&kind| self.count(kind) < QUEUE_SIZE)
+            .map(Job::Queue)
+    }
+}
+
+pub struct PuzzleQueue {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    // Stops the puzzle being generated
+    cancel: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl PuzzleQueue {
+    // Start the worker thread. It waits for the first call to want.
+    pub fn start() -> PuzzleQueue {
+        let shared = Arc::new((Mutex::new(Shared::default()), Condvar::new()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let (shared, cancel) = (shared.clone(), cancel.clone());
+            thread::spawn(move || work(&shared, &cancel))
+        };
+        PuzzleQueue { shared, cancel, worker: Some(worker) }
+    }
+
+    // Have puzzles of this kind and its neighbours made, this kind first.
+    // Everything else is dropped: the puzzles kept for it, a seeded puzzle
+    // that was not taken, and the one being made, which also goes when it is
+    // a neighbour while none of this kind is ready.
+    pub fn want(&self, kind: PuzzleKind) {
+        let (lock, condvar) = &*self.shared;
+        let mut shared = lock.lock().unwrap();
+        if shared.wanted == Some(kind) && shared.seeded.is_none() {
+            return;
+        }
+        let kept = kind.with_neighbours();
+        shared.wanted = Some(kind);
+        shared.ready.retain(|(queued, _)| kept.contains(queued));
+        shared.seeded = None;
+        shared.seeded_ready = None;
+        let cancel = match shared.working_on {
+            Some(Job::Queue(working_on)) if working_on != kind => !kept.contains(&working_on) || shared.count(kind) == 0,
+            Some(Job::Queue(_)) | None => false,
+            Some(Job::Seeded(..)) => true,
+        };
+        if cancel {
+            self.cancel.store(true, Ordering::Relaxed);
+        }
+        condvar.notify_all();
+    }
+
+    // Have the puzzle of a seed made before anything else. The puzzle being
+    // made is dropped for it, as is any other seeded one.
+    pub fn want_seeded(&self, kind: PuzzleKind, seed: u64) {
+        let (lock, condvar) = &*self.shared;
+        let mut shared = lock.lock().unwrap();
+        if shared.seeded == Some((kind, seed)) {
+            return;
+        }
+        shared.seeded = Some((kind, seed));
+        shared.seeded_ready = None;
+        if shared.working_on.is_some_and(|job| job != Job::Seeded(kind, seed)) {
+            self.cancel.store(true, Ordering::Relaxed);
+        }
+        condvar.notify_all();
+    }
+
+    // The puzzle of the seed, once the worker has made it
+    pub fn take_seeded(&self, kind: PuzzleKind, seed: u64) -> Option<GeneratedPuzzle> {
+        let (lock, condvar) = &*self.shared;
+        let mut shared = lock.lock().unwrap();
+        if shared.seeded != Some((kind, seed)) {
+            return None;
+        }
+        let puzzle = shared.seeded_ready.take()?;
+        shared.seeded = None;
+        condvar.notify_all();
+        Some(puzzle)
+    }
+
+    // A ready puzzle of the kind, the worker makes another to replace it
+    pub fn take(&self, kind: PuzzleKind) -> Option<GeneratedPuzzle> {
+        let (lock, condvar) = &*self.shared;
+        let mut shared = lock.lock().unwrap();
+        let puzzle = shared
+            .ready
+            .iter_mut()
+            .find(|(queued, _)| *queued == kind)
+            .and_then(|(_, puzzles)| puzzles.pop_front());
+        condvar.notify_all();
+        puzzle
+    }
+
+    pub fn ready(&self, kind: PuzzleKind) -> usize {
+        self.shared.0.lock().unwrap().count(kind)
+    }
+}
+
+impl Drop for PuzzleQueue {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shared;
+        lock.lock().unwrap().quit = true;
+        self.cancel.store(true, Ordering::Relaxed);
+        condvar.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn work(shared: &(Mutex<Shared>, Condvar), cancel: &AtomicBool) {
+    let (lock, condvar) = shared;
+    loop {
+        // Sleep until there is a puzzle to make
+        let job = {
+            let mut state = lock.lock().unwrap();
+            let job = loop {
+                if state.quit {
+                    return;
+                }
+                if let Some(job) = state.next_job() {
+                    break job;
+                }
+                state = condvar.wait(state).unwrap();
+            };
+            state.working_on = Some(job);
+            cancel.store(false, Ordering::Relaxed);
+            job
+        };
+
+        let puzzle = match job {
+            Job::Queue(kind) => GeneratedPuzzle::generate(kind, cancel),
+            Job::Seeded(kind, seed) => GeneratedPuzzle::generate_seeded(kind, seed, cancel),
+        };
+
+        let mut state = lock.lock().unwrap();
+        state.working_on = None;
+        match (job, puzzle) {
+            (Job::Queue(kind), Some(puzzle))
+                if state.wanted.is_some_and(|wanted| wanted.with_neighbours().contains(&kind)) =>
+            {
+                let queue = state.queue(kind);
+                if queue.len() < QUEUE_SIZE {
+                    queue.push_back(puzzle);
+                }
+            }
+            (Job::Seeded(kind, seed), Some(puzzle)) if state.seeded == Some((kind, seed)) => {
+                state.seeded_ready = Some(puzzle);
+            }
+            _ => {}
+        }
+        condvar.notify_all();
+    }
+}
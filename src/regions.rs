@@ -0,0 +1,223 @@
+use rand::{seq::SliceRandom, Rng};
+
+// Region map of the board: every cell is assigned the index of the nine-cell
+// region it belongs to. Classic sudoku uses the 3x3 boxes, jigsaw sudoku uses
+// arbitrary connected shapes. The board is made up of one or more overlapping
+// 9x9 sub-grids (five for samurai); cells outside every sub-grid are inactive.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Regions {
+    size: usize,
+    grids: Vec<(usize, usize)>,
+    map: Vec<Vec<Option<usize>>>,
+}
+
+impl Regions {
+    pub fn classic() -> Regions {
+        Self::from_grids(9, vec![(0, 0)])
+    }
+
+    // Five 9x9 grids on a 21x21 board, the centre grid sharing a corner box
+    // with each of the other four
+    pub fn samurai() -> Regions {
+        Self::from_grids(21, vec![(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)])
+    }
+
+    pub fn from_map(map: Vec<Vec<usize>>) -> Result<Regions, String> {
+        if map.len() != 9 || map.iter().any(|row| row.len() != 9) {
+            return Err("region map must be 9x9".to_string());
+        }
+
+        let regions = Regions {
+            size: 9,
+            grids: vec![(0, 0)],
+            map: map
+                .into_iter()
+                .map(|row| row.into_iter().map(Some).collect())
+                .collect(),
+        };
+        for region in 0..9 {
+            let size = regions.cells(region).len();
+            if size != 9 {
+                return Err(format!("region {} has {} cells, expected 9", region, size));
+            }
+            if !regions.is_connected(region) {
+                return Err(format!("region {} is not connected", region));
+            }
+        }
+
+        Ok(regions)
+    }
+
+    // Build a random jigsaw layout that the given solved board satisfies, by
+    // repeatedly trading cells holding the same digit between neighbouring
+    // regions, starting from the classic boxes. A trade is only kept if both
+    // regions stay connected, so every region keeps nine cells and one of each digit.
+    pub fn random_jigsaw<R: Rng>(solution: &[Vec<Option<i32>>], rng: &mut R) -> Regions {
+        let mut regions = Regions::classic();
+        let mut trades = 0;
+
+        for _ in 0..10_000 {
+            if trades == 60 {
+                break;
+            }
+
+            let row = rng.gen_range(0..9);
+            let col = rng.gen_range(0..9);
+            let from = regions.map[row][col];
+
+            let neighbours: Vec<(usize, usize)> = regions
+                .neighbours(row, col)
+                .into_iter()
+                .filter(|&(r, c)| regions.map[r][c] != from)
+                .collect();
+            let Some(&(n_row, n_col)) = neighbours.choose(rng) else {
+                continue;
+            };
+            let to = regions.map[n_row][n_col];
+
+            // Move the cell over, then hand the receiving region's cell with
+            // the same digit back
+            regions.map[row][col] = to;
+            let candidates: Vec<(usize, usize)> = regions
+                .cells(to.unwrap())
+                .into_iter()
+                .filter(|&(r, c)| {
+                    (r, c) != (row, col) &&
+                        solution[r][c] == solution[row][col] &&
+                        regions
+                            .neighbours(r, c)
+                            .into_iter()
+                            .any(|(nr, nc)| regions.map[nr][nc] == from)
+                })
+                .collect();
+            let Some(&(b_row, b_col)) = candidates.choose(rng) else {
+                regions.map[row][col] = from;
+                continue;
+            };
+            regions.map[b_row][b_col] = from;
+
+            if regions.is_connected(from.unwrap()) && regions.is_connected(to.unwrap()) {
+                trades += 1;
+            } else {
+                regions.map[row][col] = from;
+                regions.map[b_row][b_col] = to;
+            }
+        }
+
+        regions
+    }
+
+    // Width and height of the board in cells
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Top-left cell of every 9x9 sub-grid
+    pub fn grids(&self) -> &[(usize, usize)] {
+        &self.grids
+    }
+
+    pub fn is_active(&self, row: usize, col: usize) -> bool {
+        self.map[row][col].is_some()
+    }
+
+    pub fn region_of(&self, row: usize, col: usize) -> Option<usize> {
+        self.map[row][col]
+    }
+
+    pub fn cells(&self, region: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for row in 0..self.size {
+            for col in 0..self.size {
+                if self.map[row][col] == Some(region) {
+                    cells.push((row, col));
+                }
+            }
+        }
+        cells
+    }
+
+    // Every group of cells that must hold distinct digits: the rows and
+    // columns of each sub-grid, and every region
+    pub fn houses(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut houses = Vec::new();
+        for &(top, left) in &self.grids {
+            for i in 0..9 {
+                houses.push((0..9).map(|j| (top + i, left + j)).collect());
+                houses.push((0..9).map(|j| (top + j, left + i)).collect());
+            }
+        }
+
+        let mut regions: Vec<usize> = self.map.iter().flatten().flatten().copied().collect();
+        regions.sort();
+        regions.dedup();
+        for region in regions {
+            houses.push(self.cells(region));
+        }
+
+        houses
+    }
+
+    pub fn is_classic(&self) -> bool {
+        *self == Regions::classic()
+    }
+
+    // True if the two cells lie in different regions, i.e. a thick border
+    // should be drawn between them
+    pub fn is_boundary(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        self.map[a.0][a.1] != self.map[b.0][b.1]
+    }
+
+    fn from_grids(size: usize, grids: Vec<(usize, usize)>) -> Regions {
+        // Boxes are numbered by their position on the board, so the corner
+        // boxes shared between two sub-grids get a single region
+        let boxes_per_row = size / 3;
+        let mut map = vec![vec![None; size]; size];
+        for &(top, left) in &grids {
+            for (row, map_row) in map.iter_mut().enumerate().skip(top).take(9) {
+                for (col, cell) in map_row.iter_mut().enumerate().skip(left).take(9) {
+                    *cell = Some((row / 3) * boxes_per_row + col / 3);
+                }
+            }
+        }
+
+        Regions { size, grids, map }
+    }
+
+    fn is_connected(&self, region: usize) -> bool {
+        let cells = self.cells(region);
+        let Some(&start) = cells.first() else {
+            return false;
+        };
+
+        let mut visited = vec![start];
+        let mut stack = vec![start];
+        while let Some((row, col)) = stack.pop() {
+            for next in self.neighbours(row, col) {
+                if self.map[next.0][next.1] == Some(region) && !visited.contains(&next) {
+                    visited.push(next);
+                    stack.push(next);
+                }
+            }
+        }
+
+        visited.len() == cells.len()
+    }
+
+    fn neighbours(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        if row > 0 {
+            result.push((row - 1, col));
+        }
+        if row + 1 < self.size {
+            result.push((row + 1, col));
+        }
+        if col > 0 {
+            result.push((row, col - 1));
+        }
+        if col + 1 < self.size {
+            result.push((row, col + 1));
+        }
+        result
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::events::Events;
+use crate::game::{GameState, GuiData};
+use crate::input::{InputEvent, KeyChord};
+use crate::widgets;
+
+// Input scripts have one step per line, `#` starts a comment:
+//
+//   click X Y          press and release the left button at X, Y
+//   click_cell ROW COL click the middle of a cell
+//   button LABEL       click the button with that label
+//   down X Y / up X Y  press or release the left button
+//   move X Y           move the mouse, `drag` with the left and
+//   scroll_drag X Y    with the right button held
+//   wheel X Y          scroll the mouse wheel
+//   key CHORD          press and release a key, e.g. `key Shift+5`
+//   press CHORD        press or release a key on its own, to hold a
+//   release CHORD      modifier like `press Ctrl+Left Ctrl` over a click
+//   leave, focus_lost, quit
+//
+// Positions are in drawable pixels of the layout passed to replay. Recorded
+// scripts only replay the same way for the same window size and puzzle.
+
+// Feed a script into the game, step by step. Returns Ok(false) if the
+// script made the game quit.
+pub fn replay(game_state: &mut GameState, gui_data: &GuiData, script: &str) -> Result<bool, String> {
+    for (index, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let events = step_events(game_state, gui_data, line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        if !Events::process_input(game_state, gui_data, events) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn step_events(game_state: &GameState, gui_data: &GuiData, line: &str) -> Result<Vec<InputEvent>, String> {
+    let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+    let args = args.trim();
+    let click = |(x, y)| vec![InputEvent::MouseDown { x, y }, InputEvent::MouseUp { x, y }];
+    let mouse_move = |(x, y), left, right| {
+        let (last_x, last_y) = game_state.mouse_position.unwrap_or((x, y));
+        InputEvent::MouseMove { x, y, dx: x - last_x, dy: y - last_y, left, right }
+    };
+
+    let events = match command {
+        "click" => click(point(args)?),
+        "click_cell" => {
+            let (row, col) = point(args)?;
+            let size = game_state.regions.size() as i32;
+            if !(0..size).contains(&row) || !(0..size).contains(&col) {
+                return Err(format!("no cell {} {}", row, col));
+            }
+            let center = gui_data.cell_rect(game_state, row as usize, col as usize).center();
+            click((center.x(), center.y()))
+        }
+        "button" => {
+            let buttons = widgets::layout_buttons(game_state, gui_data);
+            let button = buttons
+                .iter()
+                .find(|button| button.label == args)
+                .ok_or(format!("no button labelled `{}` on screen", args))?;
+            let center = button.rect.center();
+            click((center.x(), center.y()))
+        }
+        "down" => {
+            let (x, y) = point(args)?;
+            vec![InputEvent::MouseDown { x, y }]
+        }
+        "up" => {
+            let (x, y) = point(args)?;
+            vec![InputEvent::MouseUp { x, y }]
+        }
+        "move" => vec![mouse_move(point(args)?, false, false)],
+        "drag" => vec![mouse_move(point(args)?, true, false)],
+        "scroll_drag" => vec![mouse_move(point(args)?, false, true)],
+        "wheel" => {
+            let (x, y) = point(args)?;
+            vec![InputEvent::Wheel { x, y }]
+        }
+        // Modifiers are let go together with the key
+        "key" => {
+            let chord = KeyChord::parse(args)?;
+            vec![InputEvent::KeyDown(chord), InputEvent::KeyUp(KeyChord::new(chord.key))]
+        }
+        "press" => vec![InputEvent::KeyDown(KeyChord::parse(args)?)],
+        "release" => vec![InputEvent::KeyUp(KeyChord::parse(args)?)],
+        "leave" => vec![InputEvent::MouseLeave],
+        "focus_lost" => vec![InputEvent::FocusLost],
+        "quit" => vec![InputEvent::Quit],
+        _ => {
+            return Err(format!("unknown step `{}`", command));
+        }
+    };
+    Ok(events)
+}
+
+fn point(args: &str) -> Result<(i32, i32), String> {
+    let numbers: Vec<i32> = args
+        .split_whitespace()
+        .map(|number| number.parse().map_err(|_| format!("expected a number, got `{}`", number)))
+        .collect::<Result<_, String>>()?;
+    match numbers[..] {
+        [x, y] => Ok((x, y)),
+        _ => Err(format!("expected two numbers, got `{}`", args)),
+    }
+}
+
+// The script step that replays an event, None for events scripts leave out
+pub fn script_line(event: &InputEvent) -> Option<String> {
+    let line = match *event {
+        InputEvent::MouseDown { x, y } => format!("down {} {}", x, y),
+        InputEvent::MouseUp { x, y } => format!("up {} {}", x, y),
+        InputEvent::MouseMove { x, y, right: true, .. } => format!("scroll_drag {} {}", x, y),
+        InputEvent::MouseMove { x, y, left: true, .. } => format!("drag {} {}", x, y),
+        InputEvent::MouseMove { x, y, .. } => format!("move {} {}", x, y),
+        InputEvent::Wheel { x, y } => format!("wheel {} {}", x, y),
+        InputEvent::KeyDown(chord) => format!("press {}", chord),
+        InputEvent::KeyUp(chord) => format!("release {}", chord),
+        InputEvent::MouseLeave => "leave".to_string(),
+        InputEvent::FocusLost => "focus_lost".to_string(),
+        InputEvent::Quit => "quit".to_string(),
+        InputEvent::Other => {
+            return None;
+        }
+    };
+    Some(line)
+}
+
+// Writes the input of a session as a script, see SUDOKU_RECORD in game.rs
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Recorder, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Recorder { out: BufWriter::new(file) })
+    }
+
+    pub fn record(&mut self, events: &[InputEvent]) -> Result<(), String> {
+        for line in events.iter().filter_map(script_line) {
+            writeln!(self.out, "{}", line).map_err(|e| e.to_string())?;
+        }
+        self.out.flush().map_err(|e| e.to_string())
+    }
+}
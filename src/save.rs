@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::board_generator::{BoardDifficulty, BoardLayout};
+use crate::constraints::Constraints;
+use crate::daily;
+use crate::game::{DigitSource, GameState};
+use crate::regions::Regions;
+
+// The puzzle in progress, kept in save.toml in the user's data directory
+// (~/.local/share/sudoku on Linux). Every front end writes it when it quits
+// and picks it up when it starts, so a puzzle begun in the window can be
+// finished in the terminal and the other way round. Boards are stored one
+// row per string with `.` for an empty cell.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedGame {
+    pub difficulty: BoardDifficulty,
+    pub layout: BoardLayout,
+    // Seconds spent on the puzzle so far
+    #[serde(default)]
+    pub elapsed: u64,
+    #[serde(default)]
+    pub mistakes: u32,
+    #[serde(default)]
+    pub hints: u32,
+    pub givens: Vec<String>,
+    pub board: Vec<String>,
+    pub solution: Vec<String>,
+    // Region of every cell of a jigsaw puzzle, the other layouts have fixed regions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub variants: Constraints,
+    // Set for a puzzle from the library
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+    // Set for the daily puzzle, to its date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<String>,
+    // Cells with pencil marks, a color, or a digit the player did not enter
+    #[serde(default, rename = "cell", skip_serializing_if = "Vec::is_empty")]
+    pub cells: Vec<SavedCell>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedCell {
+    pub row: usize,
+    pub col: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub corner: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub center: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<usize>,
+    // Only set for digits from a hint or the solver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<DigitSource>,
+}
+
+impl SavedGame {
+    // Where the save file lives, None if the platform has no home directory
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "sudoku").map(|dirs| dirs.data_dir().join("save.toml"))
+    }
+
+    // The puzzle being played, None if there is nothing to continue
+    pub fn from_state(game_state: &GameState) -> Option<SavedGame> {
+        if !game_state.board_initialized {
+            return None;
+        }
+
+        let size = game_state.board.len();
+        let mut cells = Vec::new();
+        for row in 0..size {
+            for col in 0..size {
+                let source = match game_state.digit_source(row, col) {
+                    Some(DigitSource::Hint) => Some(DigitSource::Hint),
+                    Some(DigitSource::Solver) => Some(DigitSource::Solver),
+                    _ => None,
+                };
+                let cell = SavedCell {
+                    row,
+                    col,
+                    corner: game_state.corner_marks[row][col].clone().unwrap_or_default(),
+                    center: game_state.center_marks[row][col].clone().unwrap_or_default(),
+                    color: game_state.cell_colors[row][col],
+                    source,
+                };
+                if !cell.corner.is_empty() || !cell.center.is_empty() || cell.color.is_some() || source.is_some() {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        let regions = match game_state.layout {
+            BoardLayout::Jigsaw => (0..size)
+                .map(|row| {
+                    (0..size)
+                        .map(|col| game_state.regions.region_of(row, col).map_or('.', |region| (b'0' + region as u8) as char))
+                        .collect()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Some(SavedGame {
+            difficulty: game_state.difficulty,
+            layout: game_state.layout,
+            elapsed: game_state.elapsed.as_secs(),
+            mistakes: game_state.mistakes,
+            hints: game_state.hints_used,
+            givens: board_rows(&game_state.initial_board),
+            board: board_rows(&game_state.board),
+            solution: board_rows(&game_state.solved_board),
+            regions,
+            variants: game_state.constraints,
+            library: game_state.library_key.clone(),
+            daily: game_state.daily.map(daily::key),
+            cells,
+        })
+    }
+
+    // Continue the saved puzzle, replacing whatever the game state held
+    pub fn restore(&self, game_state: &mut GameState) -> Result<(), String> {
+        let regions = match self.layout {
+            BoardLayout::Classic => Regions::classic(),
+            BoardLayout::Samurai => Regions::samurai(),
+            BoardLayout::Jigsaw => {
+                let map = self
+                    .regions
+                    .iter()
+                    .map(|row| {
+                        row.chars()
+                            .map(|region| region.to_digit(10).map(|region| region as usize).ok_or(format!("bad region `{}`", region)))
+                            .collect::<Result<Vec<usize>, String>>()
+                    })
+                    .collect::<Result<Vec<Vec<usize>>, String>>()?;
+                Regions::from_map(map)?
+            }
+        };
+        let size = regions.size();
+        let givens = parse_board(&self.givens, size)?;
+        let board = parse_board(&self.board, size)?;
+        let solution = parse_board(&self.solution, size)?;
+        let date = self.daily.as_deref().map(daily::parse).transpose()?;
+
+        let mut entry_sources = vec![vec![DigitSource::Player; size]; size];
+        let mut corner_marks = vec![vec![None; size]; size];
+        let mut center_marks = vec![vec![None; size]; size];
+        let mut cell_colors = vec![vec![None; size]; size];
+        for cell in &self.cells {
+            if cell.row >= size || cell.col >= size {
+                return Err(format!("no cell {} {}", cell.row, cell.col));
+            }
+            let (row, col) = (cell.row, cell.col);
+            corner_marks[row][col] = Some(cell.corner.clone()).filter(|marks| !marks.is_empty());
+            center_marks[row][col] = Some(cell.center.clone()).filter(|marks| !marks.is_empty());
+            cell_colors[row][col] = cell.color;
+            entry_sources[row][col] = cell.source.unwrap_or(DigitSource::Player);
+        }
+
+        // Everything is read, nothing below can fail and leave the game
+        // state half restored
+        game_state.difficulty = self.difficulty;
+        game_state.layout = self.layout;
+        game_state.set_rules(regions, self.variants);
+        game_state.selected_constraints = self.variants;
+        game_state.initial_board = givens;
+        game_state.board = board;
+        game_state.solved_board = solution;
+        game_state.entry_sources = entry_sources;
+        game_state.corner_marks = corner_marks;
+        game_state.center_marks = center_marks;
+        game_state.cell_colors = cell_colors;
+        // Conflicts are not saved, they follow from the board
+        game_state.invalid_positions = game_state.find_conflicts();
+
+        game_state.clear_selection();
+        game_state.board_initialized = true;
+        game_state.puzzle_solved = false;
+        game_state.completion = None;
+        game_state.library_key = self.library.clone();
+        game_state.daily = date;
+        game_state.loading = None;
+        game_state.elapsed = Duration::from_secs(self.elapsed);
+        game_state.mistakes = self.mistakes;
+        game_state.hints_used = self.hints;
+        game_state.zoom = 100;
+        game_state.scroll = (0, 0);
+        game_state.clear_history();
+        Ok(())
+    }
+
+    // Read the save file, None if there is none. A broken file is reported
+    // and ignored so the game starts with a fresh puzzle instead.
+    pub fn load() -> Option<SavedGame> {
+        let path = Self::path().filter(|path| path.exists())?;
+        Self::from_file(&path).map_err(|e| eprintln!("Ignoring saved game: {}", e)).ok()
+    }
+
+    pub fn from_file(path: &Path) -> Result<SavedGame, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<SavedGame, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Save the puzzle being played, or remove the save file if there is
+    // none, so a finished puzzle is not offered again
+    pub fn store(game_state: &GameState) -> Result<(), String> {
+        let path = Self::path().ok_or("no data directory on this platform")?;
+        match Self::from_state(game_state) {
+            Some(saved) => saved.save_to(&path),
+            None if path.exists() => fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e)),
+            None => Ok(()),
+        }
+    }
+}
+
+// Best times per layout and difficulty and the daily puzzles solved, kept in
+// records.toml next to the save file
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Records {
+    // Seconds, keyed like "classic-medium"
+    best_times: BTreeMap<String, u64>,
+    // Fastest time in seconds for each daily puzzle solved, keyed by date
+    dailies: BTreeMap<String, u64>,
+    // Dates whose daily was solved that same day, only these make a streak
+    on_the_day: BTreeSet<String>,
+}
+
+impl Records {
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "sudoku").map(|dirs| dirs.data_dir().join("records.toml"))
+    }
+
+    // Read the records, starting over if the file is missing or broken
+    pub fn load() -> Records {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Records::default();
+        };
+        Self::from_file(&path).unwrap_or_else(|e| {
+            eprintln!("Ignoring records: {}", e);
+            Records::default()
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Records, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no data directory on this platform")?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn best_time(&self, layout: BoardLayout, difficulty: BoardDifficulty) -> Option<Duration> {
+        self.best_times.get(&Self::key(layout, difficulty)).map(|&seconds| Duration::from_secs(seconds))
+    }
+
+    // Note a finished puzzle, returns the best time from before it
+    pub fn record(&mut self, layout: BoardLayout, difficulty: BoardDifficulty, time: Duration) -> Option<Duration> {
+        let previous = self.best_time(layout, difficulty);
+        if previous.is_none_or(|best| time < best) {
+            self.best_times.insert(Self::key(layout, difficulty), time.as_secs());
+        }
+        previous
+    }
+
+    pub fn daily_time(&self, date: NaiveDate) -> Option<Duration> {
+        self.dailies.get(&daily::key(date)).map(|&seconds| Duration::from_secs(seconds))
+    }
+
+    // Note a solved daily. A puzzle of an earlier day played from the
+    // calendar is kept as solved, but does not add to a streak.
+    pub fn record_daily(&mut self, date: NaiveDate, time: Duration, today: NaiveDate) {
+        let seconds = self.dailies.entry(daily::key(date)).or_insert(time.as_secs());
+        *seconds = (*seconds).min(time.as_secs());
+        if date == today {
+            self.on_the_day.insert(daily::key(date));
+        }
+    }
+
+    fn solved_on_the_day(&self, date: NaiveDate) -> bool {
+        self.on_the_day.contains(&daily::key(date))
+    }
+
+    // Days in a row with the daily puzzle solved on the day, up to today. A
+    // streak still counts while today's puzzle is waiting to be played.
+    pub fn current_streak(&self, today: NaiveDate) -> u32 {
+        let start = if self.solved_on_the_day(today) { Some(today) } else { today.pred_opt() };
+        let mut streak = 0;
+        let mut date = start;
+        while let Some(day) = date.filter(|&day| self.solved_on_the_day(day)) {
+            streak += 1;
+            date = day.pred_opt();
+        }
+        streak
+    }
+
+    pub fn longest_streak(&self) -> u32 {
+        let mut longest = 0;
+        let mut streak = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in self.on_the_day.iter().filter_map(|key| daily::parse(key).ok()) {
+            streak = if previous.and_then(|day| day.succ_opt()) == Some(date) { streak + 1 } else { 1 };
+            longest = longest.max(streak);
+            previous = Some(date);
+        }
+        longest
+    }
+
+    fn key(layout: BoardLayout, difficulty: BoardDifficulty) -> String {
+        format!("{:?}-{:?}", layout, difficulty).to_lowercase()
+    }
+}
+
+fn board_rows(board: &[Vec<Option<i32>>]) -> Vec<String> {
+    board
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.map_or('.', |digit| (b'0' + digit as u8) as char)).collect())
+        .collect()
+}
+
+fn parse_board(rows: &[String], size: usize) -> Result<Vec<Vec<Option<i32>>>, String> {
+    if rows.len() != size || rows.iter().any(|row| row.chars().count() != size) {
+        return Err(format!("expected {} rows of {} cells", size, size));
+    }
+    rows.iter()
+        .map(|row| {
+            row.chars()
+                .map(|cell| match cell {
+                    '.' => Ok(None),
+                    '1'..='9' => Ok(cell.to_digit(10).map(|digit| digit as i32)),
+                    _ => Err(format!("bad cell `{}`", cell)),
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -1,27 +1,129 @@
-use sdl2::pixels::Color;
+use std::time::Duration;
+
+use sdl2::pixels::{self, PixelFormatEnum};
 use sdl2::rect::Rect;
-use sdl2::render::TextureQuery;
+use sdl2::render::{BlendMode, Canvas, RenderTarget, TextureQuery};
+use sdl2::surface::Surface;
+use sdl2::video::Window;
+
+use crate::animation::Animations;
+use crate::game::{ Completion, DigitSource, GameState, GuiData };
+use crate::glyph_cache::{self, GlyphCache};
+use crate::theme::{Color, Theme};
+use crate::widgets;
+
+// Theme colors are drawn as they are, fully opaque
+impl From<Color> for pixels::Color {
+    fn from(color: Color) -> pixels::Color {
+        pixels::Color::RGB(color.r, color.g, color.b)
+    }
+}
+
+// How digits from one source are drawn, their colors come from the theme
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DigitStyle {
+    // Index into the loaded fonts, see glyph_cache
+    pub font: usize,
+    // Draw a short bar under the digit
+    pub underline: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DigitStyles {
+    pub given: DigitStyle,
+    pub player: DigitStyle,
+    pub hint: DigitStyle,
+    pub solver: DigitStyle,
+}
 
-use crate::board_generator;
-use crate::game::{ GameState, GuiData };
-use crate::sdl_context::SdlContext;
+impl Default for DigitStyles {
+    // Givens are bold, everything the player or the game filled in is drawn
+    // in the lighter weight, with hints underlined
+    fn default() -> Self {
+        let entry = DigitStyle { font: glyph_cache::FONT_ENTRIES, underline: false };
+        DigitStyles {
+            given: DigitStyle { font: glyph_cache::FONT_NUMBERS, underline: false },
+            player: entry,
+            hint: DigitStyle { underline: true, ..entry },
+            solver: entry,
+        }
+    }
+}
 
-pub struct ScreenRenderer {
-    pub context: SdlContext,
+impl DigitStyles {
+    pub fn for_source(&self, source: DigitSource) -> DigitStyle {
+        match source {
+            DigitSource::Given => self.given,
+            DigitSource::Player => self.player,
+            DigitSource::Hint => self.hint,
+            DigitSource::Solver => self.solver,
+        }
+    }
+}
+
+// Draws the game onto a canvas, which is the game window when playing and an
+// in-memory surface for headless rendering
+pub struct ScreenRenderer<T: RenderTarget = Window> {
+    pub canvas: Canvas<T>,
+    pub digit_styles: DigitStyles,
+    pub animations: Animations,
+    // Size of the drawable area in pixels
     pub window_width: i32,
     pub window_height: i32,
+    // Drawable pixels per window point
+    pub dpi_scale: f32,
 }
 
-impl ScreenRenderer {
-    pub fn new() -> ScreenRenderer {
-        let context = SdlContext::new();
-        let window_width = context.canvas.viewport().width() as i32;
-        let window_height = context.canvas.viewport().height() as i32;
+impl ScreenRenderer<Window> {
+    pub fn with_window(canvas: Canvas<Window>) -> ScreenRenderer {
+        let mut screen_renderer = ScreenRenderer::new(canvas);
+        screen_renderer.update_size();
+        screen_renderer
+    }
+
+    // Re-read the drawable size of the window, returns true if it changed
+    pub fn update_size(&mut self) -> bool {
+        let window = self.canvas.window();
+        let (width, height) = window.drawable_size();
+        let (points_width, _) = window.size();
+        let dpi_scale = (width as f32) / (points_width.max(1) as f32);
+
+        let changed = width as i32 != self.window_width ||
+            height as i32 != self.window_height ||
+            dpi_scale != self.dpi_scale;
+        self.window_width = width as i32;
+        self.window_height = height as i32;
+        self.dpi_scale = dpi_scale;
+        changed
+    }
+}
+
+impl ScreenRenderer<Surface<'static>> {
+    // Render into a software surface of width x height pixels instead of a
+    // window. Needs neither a display nor a GPU, so screens can be drawn in
+    // tests on any machine.
+    pub fn headless(width: u32, height: u32) -> Result<ScreenRenderer<Surface<'static>>, String> {
+        let surface = Surface::new(width, height, PixelFormatEnum::RGB888)?;
+        let canvas = surface.into_canvas()?;
+        Ok(ScreenRenderer::new(canvas))
+    }
+
+    // The rendered image as rows of RGB bytes
+    pub fn pixels(&self) -> Result<Vec<u8>, String> {
+        self.canvas.read_pixels(None, PixelFormatEnum::RGB24)
+    }
+}
 
+impl<T: RenderTarget> ScreenRenderer<T> {
+    pub fn new(canvas: Canvas<T>) -> ScreenRenderer<T> {
+        let (width, height) = canvas.output_size().unwrap_or((0, 0));
         ScreenRenderer {
-            context,
-            window_width,
-            window_height,
+            canvas,
+            digit_styles: DigitStyles::default(),
+            animations: Animations::default(),
+            window_width: width as i32,
+            window_height: height as i32,
+            dpi_scale: 1.0,
         }
     }
 
@@ -30,33 +132,42 @@ impl ScreenRenderer {
         &mut self,
         game_state: &GameState,
         fonts: Vec<&sdl2::ttf::Font>,
-        gui_data: &GuiData
+        gui_data: &GuiData,
+        glyphs: &mut GlyphCache<T::Context>
     ) -> Result<(), String> {
-        self.update();
+        self.animations.follow_cursor(game_state);
+        self.draw_bg(game_state.theme());
 
-        self.draw_bg();
-        self.draw_board(game_state, &gui_data)?;
+        // Keep a zoomed or scrolled board inside its own area
+        self.canvas.set_clip_rect(gui_data.board_area());
+        self.draw_board(game_state, gui_data)?;
 
-        // Draw the numbers
-        if game_state.board_initialized {
+        // Draw the numbers, a finished puzzle stays on screen
+        if game_state.board_initialized || game_state.puzzle_solved {
             self.draw_invalid_positions(&game_state, &gui_data)?;
-            self.draw_numbers(&game_state, &fonts, &gui_data)?;
+            self.draw_numbers(&game_state, &fonts, &gui_data, glyphs)?;
+        }
+        self.canvas.set_clip_rect(None);
+
+        if let Some(completion) = &game_state.completion {
+            self.draw_summary(completion, &fonts, gui_data, game_state.theme(), glyphs)?;
         }
-        else if !game_state.board_initialized && game_state.puzzle_solved {
-            self.display_gameover_message(&fonts[2], &gui_data)?;
+        if let Some(waited) = game_state.loading {
+            self.draw_loading(game_state, waited, &fonts, gui_data, glyphs)?;
         }
-        
-        self.draw_buttons(&game_state, fonts, gui_data)?;
 
-        self.context.canvas.present();
+        // The variant menu entries are part of the buttons and drawn last
+        self.draw_buttons(&game_state, fonts.clone(), gui_data, glyphs)?;
+
+        self.canvas.present();
 
         Ok(())
     }
 
-    fn draw_bg(&mut self) {
+    fn draw_bg(&mut self, theme: &Theme) {
         // Set the background color and clear the screen
-        self.context.canvas.set_draw_color(Color::RGB(245, 242, 232));
-        self.context.canvas.clear();
+        self.canvas.set_draw_color(theme.background);
+        self.canvas.clear();
     }
 
     fn draw_board(
@@ -65,68 +176,151 @@ impl ScreenRenderer {
         gui_data: &GuiData
     ) -> Result<(), String> {
         // Draw the board and the numbers
-        self.context.canvas.set_draw_color(Color::RGB(26, 28, 26));
-        for i in 0..9 {
-            for j in 0..9 {
-                let x = (j as i32) * (gui_data.cell_size as i32) + gui_data.offset;
-                let y = (i as i32) * (gui_data.cell_size as i32) + gui_data.offset;
-                let rect = Rect::new(x, y, gui_data.cell_size, gui_data.cell_size);
-                if Some((i, j)) == game_state.selected_square {
-                    self.context.canvas.draw_rect(rect)?;
+        let theme = game_state.theme();
+        let size = game_state.regions.size();
+        let cell_size = gui_data.board_cell_size(game_state);
+        // A sliding cursor is drawn on its own once the cells are done
+        let sliding = self.animations.cursor();
+        self.canvas.set_draw_color(theme.grid_lines);
+        for i in 0..size {
+            for j in 0..size {
+                if !game_state.regions.is_active(i, j) {
+                    continue;
+                }
+                let rect = gui_data.cell_rect(game_state, i, j);
+                let paint = game_state.cell_colors[i][j].map(|color| theme.palette[color]);
+                if game_state.is_selected(i, j) && sliding.is_none() {
+                    self.canvas.draw_rect(rect)?;
                     let selected_rect = Rect::new(
-                        x + (1 as i32),
-                        y + (1 as i32),
-                        gui_data.cell_size - 2,
-                        gui_data.cell_size - 2
+                        rect.x() + 1,
+                        rect.y() + 1,
+                        cell_size - 2,
+                        cell_size - 2
                     );
-                    self.context.canvas.set_draw_color(Color::RGB(243, 206, 161));
-                    self.context.canvas.fill_rect(selected_rect)?;
+                    self.canvas.set_draw_color(theme.selection);
+                    self.canvas.fill_rect(selected_rect)?;
+                    // A painted cell keeps its color inside the selection frame
+                    if let Some(paint) = paint {
+                        let inset = (cell_size / 8) as i32;
+                        self.canvas.set_draw_color(paint);
+                        self.canvas.fill_rect(Rect::new(
+                            rect.x() + inset,
+                            rect.y() + inset,
+                            cell_size - 2 * (inset as u32),
+                            cell_size - 2 * (inset as u32)
+                        ))?;
+                    }
+                    self.canvas.set_draw_color(theme.grid_lines);
                 } else {
-                    if game_state.initial_board[i as usize][j as usize].is_some() {
-                        self.context.canvas.set_draw_color(Color::RGB(225, 223, 216));
-                        self.context.canvas.fill_rect(rect)?;
+                    if let Some(fill) = paint.or(game_state.initial_board[i][j].map(|_| theme.given_cell)) {
+                        self.canvas.set_draw_color(fill);
+                        self.canvas.fill_rect(rect)?;
                     }
-                    self.context.canvas.set_draw_color(Color::RGB(26, 28, 26));
-                    self.context.canvas.draw_rect(rect)?;
+                    self.canvas.set_draw_color(theme.grid_lines);
+                    self.canvas.draw_rect(rect)?;
                 }
             }
         }
 
-        // Drawing thicker lines for the Sudoku's 3x3 grid
-        for i in 0..4 {
-            let x = i * gui_data.cell_size * 3 + (gui_data.offset as u32);
-            let y = i * gui_data.cell_size * 3 + (gui_data.offset as u32);
-            let line = Rect::new(x as i32, gui_data.offset, 3, gui_data.cell_size * 9);
-            let column = Rect::new(gui_data.offset, y as i32, gui_data.cell_size * 9, 3);
-            self.context.canvas.set_draw_color(Color::RGB(26, 28, 26));
-            self.context.canvas.fill_rect(line)?;
-            self.context.canvas.fill_rect(column)?;
+        if let Some((row, col)) = sliding {
+            let origin = gui_data.cell_rect(game_state, 0, 0);
+            self.canvas.set_draw_color(theme.selection);
+            self.canvas.fill_rect(Rect::new(
+                origin.x() + (col * cell_size as f32) as i32 + 1,
+                origin.y() + (row * cell_size as f32) as i32 + 1,
+                cell_size - 2,
+                cell_size - 2
+            ))?;
+        }
+
+        // Light up the cells a sweep or the completion wave is passing over,
+        // and flash new conflicts
+        if game_state.is_animating() || self.animations.is_running() {
+            let with_alpha = |color: Color, alpha: f32| pixels::Color::RGBA(color.r, color.g, color.b, (alpha * 255.0) as u8);
+            self.canvas.set_blend_mode(BlendMode::Blend);
+            for i in 0..size {
+                for j in 0..size {
+                    if !game_state.regions.is_active(i, j) {
+                        continue;
+                    }
+                    let rect = gui_data.cell_rect(game_state, i, j);
+                    let glow = game_state.completion_glow(i, j).max(self.animations.sweep(i, j));
+                    if glow > 0.0 {
+                        self.canvas.set_draw_color(with_alpha(theme.selection, glow));
+                        self.canvas.fill_rect(rect)?;
+                    }
+                    let flash = self.animations.flash(i, j);
+                    if flash > 0.0 {
+                        self.canvas.set_draw_color(with_alpha(theme.conflict, flash * 0.5));
+                        self.canvas.fill_rect(rect)?;
+                    }
+                }
+            }
+            self.canvas.set_blend_mode(BlendMode::None);
+        }
+
+        // Drawing thicker lines along the region boundaries and the outer
+        // edges of every sub-grid
+        self.canvas.set_draw_color(theme.grid_lines);
+        for i in 0..size {
+            for j in 0..size {
+                if !game_state.regions.is_active(i, j) {
+                    continue;
+                }
+                let rect = gui_data.cell_rect(game_state, i, j);
+                let (x, y) = (rect.x(), rect.y());
+
+                if j == 0 || !game_state.regions.is_active(i, j - 1) {
+                    self.canvas.fill_rect(Rect::new(x, y, 3, cell_size + 3))?;
+                }
+                if i == 0 || !game_state.regions.is_active(i - 1, j) {
+                    self.canvas.fill_rect(Rect::new(x, y, cell_size + 3, 3))?;
+                }
+                if j == size - 1 || game_state.regions.is_boundary((i, j), (i, j + 1)) {
+                    let line = Rect::new(x + (cell_size as i32), y, 3, cell_size + 3);
+                    self.canvas.fill_rect(line)?;
+                }
+                if i == size - 1 || game_state.regions.is_boundary((i, j), (i + 1, j)) {
+                    let column = Rect::new(x, y + (cell_size as i32), cell_size + 3, 3);
+                    self.canvas.fill_rect(column)?;
+                }
+            }
         }
 
         Ok(())
     }
 
+    // How far a shaking cell is pushed sideways, in pixels
+    fn shake_offset(&self, row: usize, col: usize, cell_size: u32) -> i32 {
+        (self.animations.shake(row, col) * (cell_size as f32) / 10.0) as i32
+    }
+
     fn draw_invalid_positions(&mut self, game_state: &GameState, gui_data: &GuiData) -> Result<(), String> {
         // Draw invalid positions
+        let theme = game_state.theme();
+        let cell_size = gui_data.board_cell_size(game_state);
         for (x, y, _val) in &game_state.invalid_positions {
-            let rect_x = y * (gui_data.cell_size as i32) + gui_data.offset;
-            let rect_y = x * (gui_data.cell_size as i32) + gui_data.offset;
+            let shake = self.shake_offset(*x as usize, *y as usize, cell_size);
+            let cell_rect = gui_data.cell_rect(game_state, *x as usize, *y as usize);
+            let cell_rect = Rect::new(cell_rect.x() + shake, cell_rect.y(), cell_rect.width(), cell_rect.height());
             let invalid_rect = Rect::new(
-                rect_x + 1,
-                rect_y + 1,
-                gui_data.cell_size - 2,
-                gui_data.cell_size - 2
+                cell_rect.x() + 1,
+                cell_rect.y() + 1,
+                cell_size - 2,
+                cell_size - 2
             );
             let board_rect = Rect::new(
-                rect_x + 4,
-                rect_y + 4,
-                gui_data.cell_size - 9,
-                gui_data.cell_size - 9
+                cell_rect.x() + 4,
+                cell_rect.y() + 4,
+                cell_size - 9,
+                cell_size - 9
             );
-            self.context.canvas.set_draw_color(Color::RGB(190, 0, 0));
-            self.context.canvas.fill_rect(invalid_rect)?;
-            self.context.canvas.set_draw_color(Color::RGB(245, 242, 232));
-            self.context.canvas.fill_rect(board_rect)?;
+            let inner = game_state.cell_colors[*x as usize][*y as usize]
+                .map_or(theme.background, |color| theme.palette[color]);
+            self.canvas.set_draw_color(theme.conflict);
+            self.canvas.fill_rect(invalid_rect)?;
+            self.canvas.set_draw_color(inner);
+            self.canvas.fill_rect(board_rect)?;
         }
 
         Ok(())
@@ -136,73 +330,64 @@ impl ScreenRenderer {
         &mut self,
         game_state: &GameState,
         fonts: &Vec<&sdl2::ttf::Font>,
-        gui_data: &GuiData
+        gui_data: &GuiData,
+        glyphs: &mut GlyphCache<T::Context>
     ) -> Result<(), String> {
-        // Draw the numbers
-        for i in 0..9 {
-            for j in 0..9 {
-                let x = j * (gui_data.cell_size as i32) + gui_data.offset;
-                let y = i * (gui_data.cell_size as i32) + gui_data.offset;
-
-                let texture_creator = self.context.canvas.texture_creator();
-
-                if let Some(candidates) = &game_state.candidates[i as usize][j as usize] {
-                    if !candidates.is_empty() {
-                        let mut sorted_candidates = candidates.clone();
-                        sorted_candidates.sort();
-
-                        for (idx, &val) in sorted_candidates.iter().enumerate() {
-                            let x_offset = (idx % 3) * ((gui_data.cell_size as usize) / 3);
-                            let y_offset = (idx / 3) * ((gui_data.cell_size as usize) / 3);
-
-                            let surface = fonts[3]
-                                .render(&val.to_string())
-                                .blended(Color::RGB(0, 0, 0))
-                                .map_err(|e| e.to_string())?;
-
-                            let texture = texture_creator
-                                .create_texture_from_surface(&surface)
-                                .map_err(|e| e.to_string())?;
-
-                            let TextureQuery { width, height, .. } = texture.query();
-
-                            let target = Rect::new(
-                                (x as i32) +
-                                    (x_offset as i32) +
-                                    ((gui_data.cell_size as i32) / 3 - (width as i32)) / 2,
-                                (y as i32) +
-                                    (y_offset as i32) +
-                                    ((gui_data.cell_size as i32) / 3 - (height as i32)) / 2,
-                                width,
-                                height
-                            );
-
-                            self.context.canvas.copy(&texture, None, Some(target))?;
-                        }
-                        continue;
-                    }
+        // Draw the numbers. Fonts are loaded for 9x9 cells at 100% zoom, so the
+        // glyphs are scaled to the current cell size.
+        let theme = game_state.theme();
+        let size = game_state.regions.size();
+        let cell_size = gui_data.board_cell_size(game_state) as i32;
+        let scale = |length: u32| ((length as i32) * cell_size / (gui_data.cell_size as i32)) as u32;
+
+        for i in 0..size {
+            for j in 0..size {
+                if !game_state.regions.is_active(i, j) {
+                    continue;
                 }
+                let cell_rect = gui_data.cell_rect(game_state, i, j);
+                let (x, y) = (cell_rect.x(), cell_rect.y());
 
-                if let Some(val) = game_state.board[i as usize][j as usize] {
-                    let surface = fonts[1]
-                        .render(&val.to_string())
-                        .blended(Color::RGB(0, 0, 0))
-                        .map_err(|e| e.to_string())?;
-
-                    let texture = texture_creator
-                        .create_texture_from_surface(&surface)
-                        .map_err(|e| e.to_string())?;
+                if game_state.board[i][j].is_none() {
+                    self.draw_marks(game_state, fonts, gui_data, glyphs, (i, j))?;
+                    continue;
+                }
 
+                if let (Some(val), Some(source)) = (game_state.board[i][j], game_state.digit_source(i, j)) {
+                    let style = self.digit_styles.for_source(source);
+                    let color = match source {
+                        DigitSource::Given => theme.given_digit,
+                        DigitSource::Player => theme.user_digit,
+                        DigitSource::Hint => theme.hint_digit,
+                        DigitSource::Solver => theme.solver_digit,
+                    };
+                    let texture = glyphs.get(fonts, style.font, &val.to_string(), color)?;
+
+                    // A digit that was just placed pops in, a conflicting one shakes
                     let TextureQuery { width, height, .. } = texture.query();
+                    let pop = self.animations.scale(i, j);
+                    let (width, height) = (scale(width), scale(height));
+                    let (width, height) = (((width as f32) * pop) as u32, ((height as f32) * pop) as u32);
+                    let x = x + self.shake_offset(i, j, cell_size as u32);
 
                     let target = Rect::new(
-                        (x as i32) + ((gui_data.cell_size as i32) - (width as i32)) / 2,
-                        (y as i32) + ((gui_data.cell_size as i32) - (height as i32)) / 2,
+                        x + (cell_size - (width as i32)) / 2,
+                        y + (cell_size - (height as i32)) / 2,
                         width,
                         height
                     );
 
-                    self.context.canvas.copy(&texture, None, Some(target))?;
+                    self.canvas.copy(texture, None, Some(target))?;
+
+                    if style.underline {
+                        self.canvas.set_draw_color(color);
+                        self.canvas.fill_rect(Rect::new(
+                            x + cell_size / 3,
+                            y + cell_size * 5 / 6,
+                            (cell_size / 3) as u32,
+                            (cell_size / 20).max(1) as u32
+                        ))?;
+                    }
                 }
             }
         }
@@ -210,62 +395,101 @@ impl ScreenRenderer {
         Ok(())
     }
 
+    // Pencil marks of an empty cell: corner marks fill the corners first and
+    // then the edges, center marks are written in a row across the middle
+    fn draw_marks(
+        &mut self,
+        game_state: &GameState,
+        fonts: &Vec<&sdl2::ttf::Font>,
+        gui_data: &GuiData,
+        glyphs: &mut GlyphCache<T::Context>,
+        (row, col): (usize, usize)
+    ) -> Result<(), String> {
+        const CORNER_SLOTS: [(i32, i32); 9] = [(0, 0), (2, 0), (0, 2), (2, 2), (1, 0), (1, 2), (0, 1), (2, 1), (1, 1)];
+
+        let theme = game_state.theme();
+        let cell_rect = gui_data.cell_rect(game_state, row, col);
+        let cell_size = cell_rect.width() as i32;
+        let scale = |length: u32| ((length as i32) * cell_size / (gui_data.cell_size as i32)) as u32;
+
+        if let Some(marks) = &game_state.corner_marks[row][col] {
+            let mut sorted_marks = marks.clone();
+            sorted_marks.sort();
+
+            for (&val, &(slot_x, slot_y)) in sorted_marks.iter().zip(CORNER_SLOTS.iter()) {
+                let texture = glyphs.get(fonts, glyph_cache::FONT_CANDIDATES, &val.to_string(), theme.candidate)?;
+                let TextureQuery { width, height, .. } = texture.query();
+                let (width, height) = (scale(width), scale(height));
+
+                let target = Rect::new(
+                    cell_rect.x() + slot_x * (cell_size / 3) + (cell_size / 3 - (width as i32)) / 2,
+                    cell_rect.y() + slot_y * (cell_size / 3) + (cell_size / 3 - (height as i32)) / 2,
+                    width,
+                    height
+                );
+                self.canvas.copy(texture, None, Some(target))?;
+            }
+        }
+
+        if let Some(marks) = game_state.center_marks[row][col].as_ref().filter(|marks| !marks.is_empty()) {
+            let mut sorted_marks = marks.clone();
+            sorted_marks.sort();
+            let text: String = sorted_marks.iter().map(|val| val.to_string()).collect();
+
+            let texture = glyphs.get(fonts, glyph_cache::FONT_CANDIDATES, &text, theme.candidate)?;
+            let TextureQuery { width, height, .. } = texture.query();
+            let (mut width, mut height) = (scale(width), scale(height));
+            // Long rows shrink to fit between the corner marks
+            let max_width = (cell_size * 2 / 3) as u32;
+            if width > max_width {
+                height = height * max_width / width;
+                width = max_width;
+            }
+
+            let target = Rect::new(
+                cell_rect.x() + (cell_size - (width as i32)) / 2,
+                cell_rect.y() + (cell_size - (height as i32)) / 2,
+                width,
+                height
+            );
+            self.canvas.copy(texture, None, Some(target))?;
+        }
+
+        Ok(())
+    }
+
     fn draw_buttons(
         &mut self,
         game_state: &GameState,
         fonts: Vec<&sdl2::ttf::Font>,
-        gui_data: &GuiData
+        gui_data: &GuiData,
+        glyphs: &mut GlyphCache<T::Context>
     ) -> Result<(), String> {
-        let button_names_level_1 = vec!["New Puzzle", "Candidate", "Solve"];
-        let button_states_level_1 = vec![
-            Some(game_state.new_puzzle_button_pressed),
-            Some(game_state.candidate_button_pressed),
-            Some(game_state.solve_button_pressed)
-        ];
-
-        let button_names_level_2 = vec!["Beginner", "Easy", "Medium", "Hard", "Expert"];
-        let button_difficulties_level_2 = vec![
-            Some(board_generator::BoardDifficulty::Beginner),
-            Some(board_generator::BoardDifficulty::Easy),
-            Some(board_generator::BoardDifficulty::Medium),
-            Some(board_generator::BoardDifficulty::Hard),
-            Some(board_generator::BoardDifficulty::Expert)
-        ];
-
-        for (index, (button_name, button_state)) in button_names_level_1
-            .iter()
-            .zip(button_states_level_1)
-            .enumerate() {
-            let x = gui_data.spacing_level_1 * (index as i32) + 4 * gui_data.offset;
-            self.draw_button(
-                game_state,
-                &fonts[0],
-                x,
-                gui_data.y_level_1,
-                gui_data.button_width_level_1,
-                gui_data.button_height,
-                button_name,
-                button_state,
-                None
-            )?;
+        // The settings screen and the quit dialog sit on a panel over the board
+        if let Some(entries) = widgets::panel_entries(game_state) {
+            let theme = game_state.theme();
+            let panel = gui_data.settings_panel(entries);
+            self.canvas.set_draw_color(theme.button_border);
+            self.canvas.fill_rect(panel)?;
+            self.canvas.set_draw_color(theme.background);
+            self.canvas.fill_rect(Rect::new(
+                panel.x() + 3,
+                panel.y() + 3,
+                panel.width() - 6,
+                panel.height() - 6
+            ))?;
         }
 
-        for (index, (button_name, button_difficulty)) in button_names_level_2
-            .iter()
-            .zip(button_difficulties_level_2)
-            .enumerate() {
-            let x = gui_data.spacing_level_2 * (index as i32) + 2 * gui_data.offset;
-            self.draw_button(
-                game_state,
-                &fonts[0],
-                x,
-                gui_data.y_level_2,
-                gui_data.button_width_level_2,
-                gui_data.button_height,
-                button_name,
-                None,
-                button_difficulty
-            )?;
+        let buttons = widgets::layout_buttons(game_state, gui_data);
+        let hovered = game_state.mouse_position
+            .and_then(|(x, y)| widgets::button_at(&buttons, x, y))
+            .map(|button| button.action);
+
+        for button in &buttons {
+            let is_hovered = hovered == Some(button.action);
+            // A pressed button only looks pressed while the mouse is still over it
+            let is_pressed = is_hovered && game_state.pressed_button == Some(button.action);
+            self.draw_button(&fonts, glyphs, game_state.theme(), button, is_hovered, is_pressed)?;
         }
 
         Ok(())
@@ -273,120 +497,127 @@ impl ScreenRenderer {
 
     fn draw_button(
         &mut self,
-        game_state: &GameState,
-        font: &sdl2::ttf::Font,
-        x: i32,
-        y: i32,
-        width: u32,
-        height: u32,
-        text: &str,
-        button_pressed: Option<bool>,
-        difficulty: Option<board_generator::BoardDifficulty>
+        fonts: &[&sdl2::ttf::Font],
+        glyphs: &mut GlyphCache<T::Context>,
+        theme: &Theme,
+        button: &widgets::Button,
+        hovered: bool,
+        pressed: bool
     ) -> Result<(), String> {
-        // Set the button color based on its pressed state
-        let button_color = if
-            button_pressed == Some(true) ||
-            difficulty == Some(game_state.difficulty)
-        {
-            Color::RGB(243, 206, 161)
-        } else {
-            Color::RGB(245, 242, 232)
+        // Set the button color based on its state, darkening it under the mouse
+        let button_color = match (button.active, hovered, pressed) {
+            (_, _, true) => theme.button_pressed,
+            (true, true, _) => theme.button_active_hover,
+            (true, false, _) => theme.button_active,
+            (false, true, _) => theme.button_hover,
+            (false, false, _) => theme.button,
         };
 
-        let border_color = Color::RGB(26, 28, 26);
-
-        let x_offset: u32 = 30;
-        let y_offset: u32 = 30;
+        let border_color = theme.button_border;
 
-        let border_rect = Rect::new(
-            x - ((x_offset as i32) + 10) / 2,
-            y - 25,
-            width + x_offset + 15,
-            height + y_offset + 15
-        );
-        let button_rect = Rect::new(
-            x - (x_offset as i32) / 2,
-            y - 20,
-            width + x_offset,
-            height + y_offset
-        );
+        let button_rect = button.rect;
+        let border_rect = button.outer_rect();
 
         // Draw the button border
-        self.context.canvas.set_draw_color(border_color);
-        self.context.canvas.fill_rect(border_rect)?;
+        self.canvas.set_draw_color(border_color);
+        self.canvas.fill_rect(border_rect)?;
         // Fill the button with the button color
-        self.context.canvas.set_draw_color(button_color);
-        self.context.canvas.fill_rect(button_rect)?;
-
-        // Render and draw the button text
-        let surface = font
-            .render(text)
-            .blended(Color::RGB(0, 0, 0))
-            .map_err(|e| e.to_string())?;
-
-        let texture_creator = self.context.canvas.texture_creator();
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        self.canvas.set_draw_color(button_color);
+        self.canvas.fill_rect(button_rect)?;
+        // Palette buttons show their color, framed by the highlight when picked
+        if let widgets::ButtonAction::PickColor(color) = button.action {
+            let inset = if button.active { 4 } else { 0 };
+            self.canvas.set_draw_color(theme.palette[color]);
+            self.canvas.fill_rect(Rect::new(
+                button_rect.x() + inset,
+                button_rect.y() + inset,
+                button_rect.width() - 2 * (inset as u32),
+                button_rect.height() - 2 * (inset as u32)
+            ))?;
+        }
+
+        // Draw the button text
+        let texture = glyphs.get(fonts, glyph_cache::FONT_BUTTONS, &button.label, theme.button_text)?;
         let TextureQuery { width: texture_width, height: texture_height, .. } = texture.query();
 
+        // Nudge the label down while pressed
+        let press_offset = if pressed { 1 } else { 0 };
         let target = Rect::new(
-            button_rect.x() + ((button_rect.width() as i32) - (texture_width as i32)) / 2,
-            button_rect.y() + ((button_rect.height() as i32) - (texture_height as i32)) / 2,
+            button_rect.x() + ((button_rect.width() as i32) - (texture_width as i32)) / 2 + press_offset,
+            button_rect.y() + ((button_rect.height() as i32) - (texture_height as i32)) / 2 + press_offset,
             texture_width,
             texture_height
         );
-        self.context.canvas.copy(&texture, None, Some(target))?;
+        self.canvas.copy(texture, None, Some(target))?;
 
         Ok(())
     }
 
-    fn display_gameover_message(
+    // A panel over the empty board while the next puzzle is generated, its
+    // dots counting up to show the game has not stalled
+    fn draw_loading(
         &mut self,
-        font: &sdl2::ttf::Font,
-        gui_data: &GuiData
+        game_state: &GameState,
+        waited: Duration,
+        fonts: &[&sdl2::ttf::Font],
+        gui_data: &GuiData,
+        glyphs: &mut GlyphCache<T::Context>
     ) -> Result<(), String> {
-        // Render and draw the game over message
-        let surface = font
-            .render("You solved the puzzle!")
-            .blended(Color::RGB(26, 28, 26))
-            .map_err(|e| e.to_string())?;
-
-        let texture_creator = self.context.canvas.texture_creator();
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
-        let TextureQuery { width: texture_width, height: texture_height, .. } = texture.query();
-
-        let x = gui_data.offset + (((gui_data.cell_size * 9) as i32) - (texture_width as i32)) / 2;
-        let y = gui_data.offset + (((gui_data.cell_size * 9) as i32) - (texture_height as i32)) / 2;
-        let x_offset: u32 = 30;
-        let y_offset: u32 = 30;
-        let target = Rect::new(x, y, texture_width, texture_height);
-        let bg_rect = Rect::new(
-            x - (x_offset as i32) / 2,
-            y - 20,
-            texture_width + x_offset,
-            texture_height + y_offset
-        );
-        let border_rect = Rect::new(
-            x - ((x_offset as i32) + 10) / 2,
-            y - 25,
-            texture_width + x_offset + 20,
-            texture_height + y_offset + 20
+        let theme = game_state.theme();
+        let panel = gui_data.settings_panel(1);
+        self.canvas.set_draw_color(theme.button_border);
+        self.canvas.fill_rect(panel)?;
+        self.canvas.set_draw_color(theme.background);
+        self.canvas.fill_rect(Rect::new(panel.x() + 3, panel.y() + 3, panel.width() - 6, panel.height() - 6))?;
+
+        let dots = (waited.as_millis() / 400 % 4) as usize;
+        let label = format!("Generating a {:?} puzzle{:<3}", game_state.difficulty, ".".repeat(dots));
+        let texture = glyphs.get(fonts, glyph_cache::FONT_BUTTONS, &label, theme.message_text)?;
+        let TextureQuery { width, height, .. } = texture.query();
+        let area = gui_data.settings_entries(1)[0];
+        let target = Rect::new(
+            area.x() + ((area.width() as i32) - (width as i32)) / 2,
+            area.y() + ((area.height() as i32) - (height as i32)) / 2,
+            width,
+            height
         );
-
-        self.context.canvas.set_draw_color(Color::RGB(26, 28, 26));
-        self.context.canvas.fill_rect(border_rect)?;
-        self.context.canvas.set_draw_color(Color::RGB(245, 242, 232));
-        self.context.canvas.fill_rect(bg_rect)?;
-
-        self.context.canvas.copy(&texture, None, Some(target))?;
+        self.canvas.copy(texture, None, Some(target))?;
 
         Ok(())
     }
 
+    // The summary of a finished puzzle on a panel where the second group of
+    // buttons usually is. A wide window has room for a line per entry, a
+    // tall one puts them all on one line.
+    fn draw_summary(
+        &mut self,
+        completion: &Completion,
+        fonts: &[&sdl2::ttf::Font],
+        gui_data: &GuiData,
+        theme: &Theme,
+        glyphs: &mut GlyphCache<T::Context>
+    ) -> Result<(), String> {
+        let area = gui_data.summary_area();
+        self.canvas.set_draw_color(theme.button_border);
+        self.canvas.fill_rect(Rect::new(area.x() - 4, area.y() - 4, area.width() + 8, area.height() + 8))?;
+        self.canvas.set_draw_color(theme.background);
+        self.canvas.fill_rect(area)?;
+
+        let summary = completion.summary();
+        let lines = if gui_data.landscape { summary } else { vec![summary.join("  ·  ")] };
+        let pitch = (area.height() / lines.len() as u32) as i32;
+        for (index, line) in lines.iter().enumerate() {
+            let texture = glyphs.get(fonts, glyph_cache::FONT_BUTTONS, line, theme.message_text)?;
+            let TextureQuery { width, height, .. } = texture.query();
+            let target = Rect::new(
+                area.x() + ((area.width() as i32) - (width as i32)) / 2,
+                area.y() + (index as i32) * pitch + (pitch - (height as i32)) / 2,
+                width,
+                height
+            );
+            self.canvas.copy(texture, None, Some(target))?;
+        }
 
-    // TODO: seperate out update logic
-    pub fn update(&mut self) {}
+        Ok(())
+    }
 }
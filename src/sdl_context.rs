@@ -2,14 +2,16 @@ use sdl2::render::Canvas;
 use sdl2::video::Window;
 use sdl2::EventPump;
 
+use crate::config::WindowConfig;
+
 pub struct SdlContext {
     pub canvas: Canvas<Window>,
     pub event_pump: EventPump,
 }
 
 impl SdlContext {
-    pub fn new() -> SdlContext {
-        let (event_pump, canvas) = Self::init_sdl2().unwrap();
+    pub fn new(window_config: &WindowConfig) -> SdlContext {
+        let (event_pump, canvas) = Self::init_sdl2(window_config).unwrap();
 
         SdlContext {
             event_pump,
@@ -17,22 +19,31 @@ impl SdlContext {
         }
     }
 
-    fn init_sdl2() -> Result<(EventPump, Canvas<Window>), String> {
+    fn init_sdl2(window_config: &WindowConfig) -> Result<(EventPump, Canvas<Window>), String> {
         let sdl_context = sdl2::init()?;
         let video_subsystem = sdl_context.video()?;
     
-        // Get the current display mode so we can determine screen dimensions
-        let display_mode = video_subsystem.current_display_mode(0)?;
+        // Get the usable area of the display (without task bars and docks) so we
+        // can determine the initial window dimensions
+        let bounds = video_subsystem.display_usable_bounds(0)?;
     
-        // Calculate window dimensions as percentages of screen dimensions
-        let window_width: u32 = ((display_mode.w as f32) * 0.45) as u32;
-        let window_height: u32 = ((display_mode.h as f32) * 0.95) as u32;
+        // Start with a portrait window filling most of the display height, the
+        // layout adapts to whatever size the user resizes it to
+        let height_fraction = (window_config.height_percent.clamp(20, 100) as f32) / 100.0;
+        let width_fraction = (window_config.max_width_percent.clamp(20, 100) as f32) / 100.0;
+        let window_height: u32 = ((bounds.height() as f32) * height_fraction) as u32;
+        let window_width: u32 = ((window_height as f32) * 0.85).min((bounds.width() as f32) * width_fraction) as u32;
     
-        let window = video_subsystem
+        let mut window = video_subsystem
             .window("Sudoku", window_width, window_height)
             .position_centered()
+            .resizable()
+            .allow_highdpi()
             .build()
             .map_err(|e| e.to_string())?;
+        window
+            .set_minimum_size(320, 360)
+            .map_err(|e| e.to_string())?;
     
         let canvas = window
             .into_canvas()
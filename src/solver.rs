@@ -0,0 +1,263 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::constraints::Constraints;
+use crate::regions::Regions;
+
+// Bookkeeping for a single backtracking run
+struct Search {
+    count: usize,
+    limit: usize,
+    steps: usize,
+    max_steps: usize,
+    solution: Option<Vec<Vec<Option<i32>>>>,
+    // Digits already placed in each house, bit n set for digit n
+    used: Vec<u16>,
+}
+
+// Backtracking solver that respects the board's region layout and any variant
+// constraints. Cells are picked most-constrained first, which keeps uniqueness
+// checks fast enough to run for every clue removed by the generator.
+#[derive(Clone)]
+pub struct Solver {
+    regions: Regions,
+    constraints: Constraints,
+    houses: Vec<Vec<(usize, usize)>>,
+    // Indices into houses for every cell
+    cell_houses: Vec<Vec<Vec<usize>>>,
+    // Cells that may not repeat the digit because of anti-knight/anti-king
+    constraint_peers: Vec<Vec<Vec<(usize, usize)>>>,
+}
+
+impl Solver {
+    pub fn new(regions: Regions) -> Solver {
+        Self::new_with_constraints(regions, Constraints::default())
+    }
+
+    pub fn new_with_constraints(regions: Regions, constraints: Constraints) -> Solver {
+        let size = regions.size();
+        let houses = regions.houses();
+
+        let mut cell_houses: Vec<Vec<Vec<usize>>> = vec![vec![Vec::new(); size]; size];
+        for (index, house) in houses.iter().enumerate() {
+            for &(row, col) in house {
+                cell_houses[row][col].push(index);
+            }
+        }
+
+        let mut constraint_peers: Vec<Vec<Vec<(usize, usize)>>> = vec![vec![Vec::new(); size]; size];
+        for (row, row_peers) in constraint_peers.iter_mut().enumerate() {
+            for (col, cell_peers) in row_peers.iter_mut().enumerate() {
+                if regions.is_active(row, col) {
+                    *cell_peers = constraints
+                        .peers(row, col, size)
+                        .into_iter()
+                        .filter(|&(r, c)| regions.is_active(r, c))
+                        .collect();
+                }
+            }
+        }
+
+        Solver { regions, constraints, houses, cell_houses, constraint_peers }
+    }
+
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    pub fn constraints(&self) -> Constraints {
+        self.constraints
+    }
+
+    // Check if num can be placed at (row, col) without clashing with another
+    // cell in the same row, column or region of any sub-grid the cell is part
+    // of, or breaking a variant constraint
+    pub fn is_valid(&self, board: &[Vec<Option<i32>>], row: usize, col: usize, num: i32) -> bool {
+        let clashes = |&(r, c): &(usize, usize)| (r, c) != (row, col) && board[r][c] == Some(num);
+
+        !self.cell_houses[row][col]
+            .iter()
+            .any(|&house| self.houses[house].iter().any(clashes)) &&
+            !self.constraint_peers[row][col].iter().any(clashes) &&
+            self.constraints.allows(board, row, col, num)
+    }
+
+    // True if every cell is filled without breaking a rule, whether or not
+    // the board is the solution the puzzle was made with
+    pub fn is_complete(&self, board: &[Vec<Option<i32>>]) -> bool {
+        let size = self.regions.size();
+        (0..size)
+            .flat_map(|row| (0..size).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.regions.is_active(row, col))
+            .all(|(row, col)| board[row][col].is_some_and(|num| self.is_valid(board, row, col, num)))
+    }
+
+    pub fn solve(&self, board: &[Vec<Option<i32>>]) -> Option<Vec<Vec<Option<i32>>>> {
+        let mut search = self.start_search(board, 1, usize::MAX);
+        self.search(&mut board.to_vec(), &mut search);
+        search.solution
+    }
+
+    // Count the solutions of the board, stopping once limit is reached
+    pub fn count_solutions(&self, board: &[Vec<Option<i32>>], limit: usize) -> usize {
+        self.count_solutions_within(board, limit, usize::MAX).unwrap_or(limit)
+    }
+
+    // Same as count_solutions, but gives up and returns None after max_steps
+    // placements. Loosely coupled boards such as samurai can otherwise take
+    // minutes to prove that a candidate puzzle is not unique.
+    pub fn count_solutions_within(
+        &self,
+        board: &[Vec<Option<i32>>],
+        limit: usize,
+        max_steps: usize
+    ) -> Option<usize> {
+        let mut search = self.start_search(board, limit, max_steps);
+        if self.search(&mut board.to_vec(), &mut search) {
+            Some(search.count)
+        } else {
+            None
+        }
+    }
+
+    // Fill the empty cells of the board with a random valid solution. Gives up
+    // after max_steps placements so unsolvable layouts can be discarded quickly.
+    pub fn fill_random<R: Rng>(
+        &self,
+        board: &mut Vec<Vec<Option<i32>>>,
+        rng: &mut R,
+        max_steps: usize
+    ) -> bool {
+        let mut search = self.start_search(board, 1, max_steps);
+        self.fill_step(board, rng, &mut search)
+    }
+
+    fn start_search(&self, board: &[Vec<Option<i32>>], limit: usize, max_steps: usize) -> Search {
+        let used = self.houses
+            .iter()
+            .map(|house| {
+                house
+                    .iter()
+                    .filter_map(|&(row, col)| board[row][col])
+                    .fold(0, |mask, val| mask | (1 << val))
+            })
+            .collect();
+
+        Search { count: 0, limit, steps: 0, max_steps, solution: None, used }
+    }
+
+    fn fill_step<R: Rng>(
+        &self,
+        board: &mut Vec<Vec<Option<i32>>>,
+        rng: &mut R,
+        search: &mut Search
+    ) -> bool {
+        let Some((row, col, mask)) = self.most_constrained(board, &search.used) else {
+            return true;
+        };
+
+        let mut numbers = Self::digits(mask);
+        numbers.shuffle(rng);
+        for num in numbers {
+            search.steps += 1;
+            if search.steps > search.max_steps {
+                return false;
+            }
+            self.place(board, &mut search.used, row, col, num);
+            if self.fill_step(board, rng, search) {
+                return true;
+            }
+            self.unplace(board, &mut search.used, row, col, num);
+        }
+
+        false
+    }
+
+    // Returns false if the search was aborted after running out of steps
+    fn search(&self, board: &mut Vec<Vec<Option<i32>>>, search: &mut Search) -> bool {
+        let Some((row, col, mask)) = self.most_constrained(board, &search.used) else {
+            search.count += 1;
+            if search.solution.is_none() {
+                search.solution = Some(board.clone());
+            }
+            return true;
+        };
+
+        for num in Self::digits(mask) {
+            search.steps += 1;
+            if search.steps > search.max_steps {
+                return false;
+            }
+            self.place(board, &mut search.used, row, col, num);
+            let finished = self.search(board, search);
+            self.unplace(board, &mut search.used, row, col, num);
+            if !finished {
+                return false;
+            }
+            if search.count >= search.limit {
+                break;
+            }
+        }
+
+        true
+    }
+
+    fn place(&self, board: &mut [Vec<Option<i32>>], used: &mut [u16], row: usize, col: usize, num: i32) {
+        board[row][col] = Some(num);
+        for &house in &self.cell_houses[row][col] {
+            used[house] |= 1 << num;
+        }
+    }
+
+    fn unplace(&self, board: &mut [Vec<Option<i32>>], used: &mut [u16], row: usize, col: usize, num: i32) {
+        board[row][col] = None;
+        for &house in &self.cell_houses[row][col] {
+            used[house] &= !(1 << num);
+        }
+    }
+
+    // Find the empty cell with the fewest candidates, as a bit mask of digits.
+    // Returns None once the board is full; a cell without candidates is
+    // returned with an empty mask.
+    fn most_constrained(&self, board: &[Vec<Option<i32>>], used: &[u16]) -> Option<(usize, usize, u16)> {
+        let mut best: Option<(usize, usize, u16)> = None;
+        for row in 0..self.regions.size() {
+            for col in 0..self.regions.size() {
+                if board[row][col].is_some() || !self.regions.is_active(row, col) {
+                    continue;
+                }
+                let mask = self.candidates(board, used, row, col);
+                if mask.count_ones() < 2 {
+                    return Some((row, col, mask));
+                }
+                if best.is_none_or(|(_, _, b)| mask.count_ones() < b.count_ones()) {
+                    best = Some((row, col, mask));
+                }
+            }
+        }
+        best
+    }
+
+    fn candidates(&self, board: &[Vec<Option<i32>>], used: &[u16], row: usize, col: usize) -> u16 {
+        let mut mask: u16 = 0b11_1111_1110;
+        for &house in &self.cell_houses[row][col] {
+            mask &= !used[house];
+        }
+        for &(r, c) in &self.constraint_peers[row][col] {
+            if let Some(val) = board[r][c] {
+                mask &= !(1 << val);
+            }
+        }
+        if self.constraints.non_consecutive && mask != 0 {
+            for num in Self::digits(mask) {
+                if !self.constraints.allows(board, row, col, num) {
+                    mask &= !(1 << num);
+                }
+            }
+        }
+        mask
+    }
+
+    fn digits(mask: u16) -> Vec<i32> {
+        (1..=9).filter(|&num| mask & (1 << num) != 0).collect()
+    }
+}
@@ -0,0 +1,263 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Number of colors the player can paint cells with
+pub const PALETTE_SIZE: usize = 5;
+
+// An opaque color. The theme is shared with the terminal, so it is not tied
+// to SDL; the window converts it when drawing, see screen_renderer.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+}
+
+// Every color the renderer uses. Custom themes are plain text files with one
+// `key = #rrggbb` line per color, any color left out is taken from the base
+// theme (light unless a `base = dark` or `base = high-contrast` line is given).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub grid_lines: Color,
+    pub given_cell: Color,
+    pub given_digit: Color,
+    pub user_digit: Color,
+    pub hint_digit: Color,
+    pub solver_digit: Color,
+    pub candidate: Color,
+    pub selection: Color,
+    pub conflict: Color,
+    pub button: Color,
+    pub button_hover: Color,
+    pub button_active: Color,
+    pub button_active_hover: Color,
+    pub button_pressed: Color,
+    pub button_border: Color,
+    pub button_text: Color,
+    pub message_text: Color,
+    // Cell colors for the coloring tool, `palette_1` to `palette_5` in theme files
+    pub palette: [Color; PALETTE_SIZE],
+}
+
+impl Theme {
+    pub fn light() -> Theme {
+        Theme {
+            name: "Light".to_string(),
+            background: Color::rgb(245, 242, 232),
+            grid_lines: Color::rgb(26, 28, 26),
+            given_cell: Color::rgb(225, 223, 216),
+            given_digit: Color::rgb(0, 0, 0),
+            user_digit: Color::rgb(36, 76, 150),
+            hint_digit: Color::rgb(30, 110, 60),
+            solver_digit: Color::rgb(120, 120, 120),
+            candidate: Color::rgb(0, 0, 0),
+            selection: Color::rgb(243, 206, 161),
+            conflict: Color::rgb(190, 0, 0),
+            button: Color::rgb(245, 242, 232),
+            button_hover: Color::rgb(230, 226, 212),
+            button_active: Color::rgb(243, 206, 161),
+            button_active_hover: Color::rgb(232, 190, 140),
+            button_pressed: Color::rgb(214, 170, 120),
+            button_border: Color::rgb(26, 28, 26),
+            button_text: Color::rgb(0, 0, 0),
+            message_text: Color::rgb(26, 28, 26),
+            palette: [
+                Color::rgb(240, 170, 170),
+                Color::rgb(245, 215, 130),
+                Color::rgb(170, 220, 160),
+                Color::rgb(160, 200, 240),
+                Color::rgb(210, 180, 235),
+            ],
+        }
+    }
+
+    pub fn dark() -> Theme {
+        Theme {
+            name: "Dark".to_string(),
+            background: Color::rgb(30, 32, 36),
+            grid_lines: Color::rgb(200, 200, 190),
+            given_cell: Color::rgb(48, 51, 58),
+            given_digit: Color::rgb(235, 235, 225),
+            user_digit: Color::rgb(120, 180, 255),
+            hint_digit: Color::rgb(120, 210, 140),
+            solver_digit: Color::rgb(130, 130, 125),
+            candidate: Color::rgb(170, 170, 160),
+            selection: Color::rgb(90, 78, 50),
+            conflict: Color::rgb(220, 60, 60),
+            button: Color::rgb(45, 48, 54),
+            button_hover: Color::rgb(60, 64, 72),
+            button_active: Color::rgb(120, 95, 55),
+            button_active_hover: Color::rgb(140, 110, 65),
+            button_pressed: Color::rgb(160, 125, 70),
+            button_border: Color::rgb(200, 200, 190),
+            button_text: Color::rgb(235, 235, 225),
+            message_text: Color::rgb(235, 235, 225),
+            palette: [
+                Color::rgb(110, 50, 50),
+                Color::rgb(110, 90, 35),
+                Color::rgb(45, 95, 55),
+                Color::rgb(40, 75, 115),
+                Color::rgb(85, 60, 115),
+            ],
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "High Contrast".to_string(),
+            background: Color::rgb(255, 255, 255),
+            grid_lines: Color::rgb(0, 0, 0),
+            given_cell: Color::rgb(210, 210, 210),
+            given_digit: Color::rgb(0, 0, 0),
+            user_digit: Color::rgb(0, 0, 200),
+            hint_digit: Color::rgb(0, 120, 0),
+            solver_digit: Color::rgb(90, 90, 90),
+            candidate: Color::rgb(0, 0, 0),
+            selection: Color::rgb(255, 220, 0),
+            conflict: Color::rgb(255, 0, 0),
+            button: Color::rgb(255, 255, 255),
+            button_hover: Color::rgb(220, 220, 220),
+            button_active: Color::rgb(255, 220, 0),
+            button_active_hover: Color::rgb(235, 200, 0),
+            button_pressed: Color::rgb(200, 170, 0),
+            button_border: Color::rgb(0, 0, 0),
+            button_text: Color::rgb(0, 0, 0),
+            message_text: Color::rgb(0, 0, 0),
+            palette: [
+                Color::rgb(255, 140, 140),
+                Color::rgb(255, 200, 60),
+                Color::rgb(120, 230, 120),
+                Color::rgb(120, 190, 255),
+                Color::rgb(220, 150, 255),
+            ],
+        }
+    }
+
+    pub fn built_in() -> Vec<Theme> {
+        vec![Theme::light(), Theme::dark(), Theme::high_contrast()]
+    }
+
+    // Every *.theme file in the directory, in name order. Broken files are
+    // reported and skipped rather than stopping the game.
+    pub fn load_dir(dir: &Path) -> Vec<Theme> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "theme"))
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .filter_map(|path| {
+                Theme::from_file(path)
+                    .map_err(|e| eprintln!("Skipping theme {}", e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    pub fn from_file(path: &Path) -> Result<Theme, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut theme = Theme::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        // Unnamed themes are called after their file
+        if theme.name.is_empty() {
+            theme.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        Ok(theme)
+    }
+
+    pub fn parse(text: &str) -> Result<Theme, String> {
+        let lines: Vec<(usize, &str, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or(format!("line {}: expected `key = value`", number))?;
+                Ok((number, key.trim(), value.trim()))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let mut theme = match lines.iter().find(|(_, key, _)| *key == "base") {
+            None => Theme::light(),
+            Some((_, _, "light")) => Theme::light(),
+            Some((_, _, "dark")) => Theme::dark(),
+            Some((_, _, "high-contrast")) => Theme::high_contrast(),
+            Some((number, _, base)) => {
+                return Err(format!("line {}: unknown base theme `{}`", number, base));
+            }
+        };
+        theme.name = String::new();
+
+        for (number, key, value) in lines {
+            match key {
+                "base" => {}
+                "name" => {
+                    theme.name = value.to_string();
+                }
+                _ => {
+                    let color = theme
+                        .color_mut(key)
+                        .ok_or(format!("line {}: unknown color `{}`", number, key))?;
+                    *color = Self::parse_color(value)
+                        .ok_or(format!("line {}: expected a color like #1a1c1a, got `{}`", number, value))?;
+                }
+            }
+        }
+
+        Ok(theme)
+    }
+
+    fn color_mut(&mut self, key: &str) -> Option<&mut Color> {
+        let color = match key {
+            "background" => &mut self.background,
+            "grid_lines" => &mut self.grid_lines,
+            "given_cell" => &mut self.given_cell,
+            "given_digit" => &mut self.given_digit,
+            "user_digit" => &mut self.user_digit,
+            "hint_digit" => &mut self.hint_digit,
+            "solver_digit" => &mut self.solver_digit,
+            "candidate" => &mut self.candidate,
+            "selection" => &mut self.selection,
+            "conflict" => &mut self.conflict,
+            "button" => &mut self.button,
+            "button_hover" => &mut self.button_hover,
+            "button_active" => &mut self.button_active,
+            "button_active_hover" => &mut self.button_active_hover,
+            "button_pressed" => &mut self.button_pressed,
+            "button_border" => &mut self.button_border,
+            "button_text" => &mut self.button_text,
+            "message_text" => &mut self.message_text,
+            _ => {
+                let index = key.strip_prefix("palette_")?.parse::<usize>().ok()?;
+                self.palette.get_mut(index.checked_sub(1)?)?
+            }
+        };
+        Some(color)
+    }
+
+    fn parse_color(value: &str) -> Option<Color> {
+        let hex = value.strip_prefix('#')?;
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Color::rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
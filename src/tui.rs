@@ -0,0 +1,605 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{self, Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, queue, terminal};
+
+use crate::config::{self, Config};
+use crate::daily;
+use crate::game::{self, DigitSource, GameEvent, GameState, MarkKind};
+use crate::input::{InputAction, KeyChord};
+use crate::library::Library;
+use crate::puzzle_queue::PuzzleQueue;
+use crate::save::{Records, SavedGame};
+use crate::theme::{Color, Theme};
+
+// Terminal front end for playing over SSH or anywhere else without a display.
+// It runs on the same GameState API, config file, key bindings and save file
+// as the SDL window, drawing the board with box-drawing characters.
+
+// Box-drawing character for every junction of grid lines, indexed by the
+// weight of its up, right, down and left arm (0 none, 1 light, 2 heavy) as
+// up * 27 + right * 9 + down * 3 + left
+const JUNCTIONS: &str = " ╴╸╷┐┑╻┒┓╶─╾┌┬┭┎┰┱╺╼━┍┮┯┏┲┳╵┘┙│┤┥╽┧┪└┴┵├┼┽┟╁╅┕┶┷┝┾┿┢╆╈╹┚┛╿┦┩┃┨┫┖┸┹┞╀╃┠╂╉┗┺┻┡╄╇┣╊╋";
+
+// Corner marks are written as superscripts to tell them from center marks
+const SUPERSCRIPTS: [char; 9] = ['¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+// Each cell is three characters wide plus one for the line to its left, and
+// one line tall plus the line above it
+const CELL_WIDTH: usize = 4;
+const CELL_HEIGHT: usize = 2;
+// Where the top-left corner of the board goes
+const BOARD_X: usize = 2;
+const BOARD_Y: usize = 2;
+
+// One character on the terminal
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TermCell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+// Everything on the terminal for one frame, drawn off screen first so it can
+// be checked in tests and written out in one go
+pub struct Screen {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<TermCell>,
+}
+
+impl Screen {
+    pub fn new(width: usize, height: usize, theme: &Theme) -> Screen {
+        let blank = TermCell { ch: ' ', fg: theme.message_text, bg: theme.background, bold: false, underline: false };
+        Screen { width, height, cells: vec![blank; width * height] }
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> TermCell {
+        self.cells[y * self.width + x]
+    }
+
+    // The characters of a line without their colors, trailing spaces removed
+    pub fn line(&self, y: usize) -> String {
+        let line: String = self.cells[y * self.width..(y + 1) * self.width].iter().map(|cell| cell.ch).collect();
+        line.trim_end().to_string()
+    }
+
+    fn put(&mut self, x: usize, y: usize, cell: TermCell) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = cell;
+        }
+    }
+
+    // Write text in the given colors, cut off at the right edge
+    fn print(&mut self, x: usize, y: usize, text: &str, fg: Color, bg: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(x + i, y, TermCell { ch, fg, bg, bold: false, underline: false });
+        }
+    }
+}
+
+// Terminal size the board of the current layout needs
+pub fn required_size(game_state: &GameState) -> (usize, usize) {
+    let size = game_state.regions.size();
+    (BOARD_X * 2 + size * CELL_WIDTH + 1, BOARD_Y + size * CELL_HEIGHT + 1 + 4)
+}
+
+// Lay out a frame: a status line, the board, the pencil marks of the cursor
+// cell and a line of help
+pub fn draw(game_state: &GameState, width: usize, height: usize) -> Screen {
+    let theme = game_state.theme();
+    let mut screen = Screen::new(width, height, theme);
+    let (needed_width, needed_height) = required_size(game_state);
+    if width < needed_width || height < needed_height {
+        let message = format!("Make the terminal at least {}x{} to play", needed_width, needed_height);
+        screen.print(0, 0, &message, theme.message_text, theme.background);
+        return screen;
+    }
+
+    let status = match game_state.daily {
+        Some(date) => format!(
+            "Sudoku  Daily {} {:?}  {}",
+            daily::key(date),
+            game_state.difficulty,
+            game::format_time(game_state.elapsed)
+        ),
+        None => format!(
+            "Sudoku  {:?} {:?}  {}",
+            game_state.difficulty,
+            game_state.layout,
+            game::format_time(game_state.elapsed)
+        ),
+    };
+    screen.print(BOARD_X, 0, &status, theme.message_text, theme.background);
+
+    let below = BOARD_Y + game_state.regions.size() * CELL_HEIGHT + 2;
+    if let Some(completion) = &game_state.completion {
+        // The solved board stays up with the summary underneath
+        draw_board(&mut screen, game_state);
+        screen.print(BOARD_X, below, &completion.summary().join("  "), theme.message_text, theme.background);
+    } else if game_state.board_initialized {
+        draw_board(&mut screen, game_state);
+        screen.print(BOARD_X, below, &cursor_marks(game_state), theme.candidate, theme.background);
+    } else if let Some(waited) = game_state.loading {
+        let dots = ".".repeat((waited.as_millis() / 400 % 4) as usize);
+        let message = format!("Generating a {:?} puzzle{}", game_state.difficulty, dots);
+        screen.print(BOARD_X, BOARD_Y, &message, theme.message_text, theme.background);
+    } else {
+        screen.print(BOARD_X, BOARD_Y, "Welcome to Sudoku", theme.message_text, theme.background);
+    }
+
+    screen.print(BOARD_X, below + 1, &mode_line(game_state), theme.message_text, theme.background);
+    screen.print(BOARD_X, below + 2, help_line(game_state), theme.message_text, theme.background);
+    screen
+}
+
+fn draw_board(screen: &mut Screen, game_state: &GameState) {
+    let theme = game_state.theme();
+    let size = game_state.regions.size();
+    let junctions: Vec<char> = JUNCTIONS.chars().collect();
+
+    for row in 0..=size {
+        for col in 0..=size {
+            let (x, y) = (BOARD_X + col * CELL_WIDTH, BOARD_Y + row * CELL_HEIGHT);
+            let up = vertical_line(game_state, row as i32 - 1, col);
+            let down = vertical_line(game_state, row as i32, col);
+            let left = horizontal_line(game_state, row, col as i32 - 1);
+            let right = horizontal_line(game_state, row, col as i32);
+            let junction = junctions[up * 27 + right * 9 + down * 3 + left];
+            screen.print(x, y, &junction.to_string(), theme.grid_lines, theme.background);
+
+            let across = [' ', '─', '━'][right];
+            screen.print(x + 1, y, &across.to_string().repeat(CELL_WIDTH - 1), theme.grid_lines, theme.background);
+            let below = [' ', '│', '┃'][down];
+            screen.print(x, y + 1, &below.to_string(), theme.grid_lines, theme.background);
+        }
+    }
+
+    for row in 0..size {
+        for col in 0..size {
+            if game_state.regions.is_active(row, col) {
+                draw_cell(screen, game_state, row, col);
+            }
+        }
+    }
+}
+
+// Weight of the line left of a cell, 0 if the row is off the board
+fn vertical_line(game_state: &GameState, row: i32, col: usize) -> usize {
+    if row < 0 || row as usize >= game_state.regions.size() {
+        return 0;
+    }
+    line_weight(game_state, (row as usize, col.wrapping_sub(1)), (row as usize, col))
+}
+
+// Weight of the line above a cell, 0 if the column is off the board
+fn horizontal_line(game_state: &GameState, row: usize, col: i32) -> usize {
+    if col < 0 || col as usize >= game_state.regions.size() {
+        return 0;
+    }
+    line_weight(game_state, (row.wrapping_sub(1), col as usize), (row, col as usize))
+}
+
+// Heavy between regions and around the board, light between the cells of a
+// region, none where neither side is part of the board
+fn line_weight(game_state: &GameState, a: (usize, usize), b: (usize, usize)) -> usize {
+    let size = game_state.regions.size();
+    let active = |(row, col): (usize, usize)| row < size && col < size && game_state.regions.is_active(row, col);
+    match (active(a), active(b)) {
+        (false, false) => 0,
+        (true, true) if !game_state.regions.is_boundary(a, b) => 1,
+        _ => 2,
+    }
+}
+
+fn draw_cell(screen: &mut Screen, game_state: &GameState, row: usize, col: usize) {
+    let theme = game_state.theme();
+    let digit = game_state.board[row][col];
+    let mut bg = match game_state.cell_colors[row][col] {
+        Some(color) => theme.palette[color],
+        None if game_state.initial_board[row][col].is_some() => theme.given_cell,
+        None => theme.background,
+    };
+    // Cells light up while the completion wave passes over them
+    if game_state.is_selected(row, col) || game_state.completion_glow(row, col) > 0.5 {
+        bg = theme.selection;
+    }
+
+    let (text, fg) = match digit {
+        Some(digit) => {
+            let conflict = game_state.invalid_positions.contains(&(row as i32, col as i32, digit));
+            let fg = match game_state.digit_source(row, col) {
+                _ if conflict => theme.conflict,
+                Some(DigitSource::Given) => theme.given_digit,
+                Some(DigitSource::Hint) => theme.hint_digit,
+                Some(DigitSource::Solver) => theme.solver_digit,
+                _ => theme.user_digit,
+            };
+            (format!(" {} ", digit), fg)
+        }
+        None => (cell_marks(game_state, row, col), theme.candidate),
+    };
+
+    // Digits matching the one under the cursor stand out
+    let cursor_digit = game_state
+        .selected_square
+        .and_then(|(row, col)| game_state.board[row as usize][col as usize]);
+    let matches = digit.is_some() && digit == cursor_digit;
+
+    let (x, y) = (BOARD_X + col * CELL_WIDTH + 1, BOARD_Y + row * CELL_HEIGHT + 1);
+    for (i, ch) in text.chars().enumerate() {
+        let cell = TermCell {
+            ch,
+            fg,
+            bg,
+            bold: game_state.initial_board[row][col].is_some() || matches,
+            underline: matches && ch != ' ',
+        };
+        screen.put(x + i, y, cell);
+    }
+}
+
+// What an empty cell shows of its pencil marks in three characters: the
+// center marks if it has any, otherwise the corner marks as superscripts
+fn cell_marks(game_state: &GameState, row: usize, col: usize) -> String {
+    let center = game_state.center_marks[row][col].clone().unwrap_or_default();
+    let corner = game_state.corner_marks[row][col].clone().unwrap_or_default();
+    let marks: Vec<char> = if !center.is_empty() {
+        center.iter().filter_map(|&digit| char::from_digit(digit as u32, 10)).collect()
+    } else {
+        corner.iter().map(|&digit| SUPERSCRIPTS[(digit - 1) as usize]).collect()
+    };
+
+    match marks.len() {
+        0 => "   ".to_string(),
+        1 => format!(" {} ", marks[0]),
+        2 => format!("{}{} ", marks[0], marks[1]),
+        3 => marks.iter().collect(),
+        _ => format!("{}{}…", marks[0], marks[1]),
+    }
+}
+
+// All pencil marks of the cell under the cursor, which the board may have
+// had to shorten
+fn cursor_marks(game_state: &GameState) -> String {
+    let Some((row, col)) = game_state.selected_square else {
+        return String::new();
+    };
+    let (row, col) = (row as usize, col as usize);
+    let list = |marks: &Option<Vec<i32>>| {
+        let mut marks = marks.clone().unwrap_or_default();
+        marks.sort();
+        marks.iter().map(|digit| digit.to_string()).collect::<Vec<_>>().join(" ")
+    };
+    let corner = list(&game_state.corner_marks[row][col]);
+    let center = list(&game_state.center_marks[row][col]);
+
+    let mut text = format!("Row {} column {}", row + 1, col + 1);
+    if !corner.is_empty() {
+        text += &format!("  corner {}", corner);
+    }
+    if !center.is_empty() {
+        text += &format!("  center {}", center);
+    }
+    text
+}
+
+fn mode_line(game_state: &GameState) -> String {
+    if game_state.color_mode {
+        "Coloring: 1-5 paint the selected cells, K to stop".to_string()
+    } else if game_state.candidate_button_pressed {
+        "Candidate mode: digits are corner marks, C to stop".to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn help_line(game_state: &GameState) -> &'static str {
+    if game_state.quit_requested {
+        "Quit? Escape again quits, any other key keeps playing"
+    } else if let Some(completion) = &game_state.completion {
+        if completion.shared {
+            "Copied  N same difficulty  Enter start screen  Escape quit"
+        } else {
+            "N same difficulty  Enter start screen  S share  Escape quit"
+        }
+    } else if !game_state.board_initialized {
+        "N new puzzle  D daily puzzle  Escape quit"
+    } else {
+        "Arrows move  Shift+digit corner  Alt+digit center  H hint  Ctrl+Z undo  N new  Escape quit"
+    }
+}
+
+// The key chord a terminal key press stands for. Terminals report the
+// character a key produced, so shifted digits arrive as symbols (on a US
+// layout) and Ctrl with a digit is not reported at all.
+pub fn key_chord(key: KeyEvent) -> Option<KeyChord> {
+    let mut shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let name = match key.code {
+        KeyCode::Char(ch) => {
+            if let Some(index) = "!@#$%^&*(".find(ch) {
+                shift = true;
+                (index + 1).to_string()
+            } else if ch == ' ' {
+                "Space".to_string()
+            } else {
+                shift |= ch.is_ascii_uppercase();
+                ch.to_ascii_uppercase().to_string()
+            }
+        }
+        KeyCode::F(number) => format!("F{}", number),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Delete => "Delete".to_string(),
+        KeyCode::Esc => "Escape".to_string(),
+        KeyCode::Enter => "Return".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        _ => {
+            return None;
+        }
+    };
+
+    let mut chord = KeyChord::parse(&name).ok()?;
+    chord.shift = shift;
+    chord.ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    chord.alt = key.modifiers.contains(KeyModifiers::ALT);
+    Some(chord)
+}
+
+// Apply a key press, returns false once the game should quit
+pub fn handle_key(game_state: &mut GameState, key: KeyEvent) -> bool {
+    // Raw mode swallows the usual Ctrl+C
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return false;
+    }
+    // The completion screen's buttons, the other keys keep working
+    if game_state.completion.is_some() && !game_state.quit_requested {
+        match key.code {
+            KeyCode::Enter => {
+                game_state.close_completion();
+                return true;
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                game_state.clipboard = game_state.share();
+                return true;
+            }
+            _ => {}
+        }
+    }
+    let Some(chord) = key_chord(key) else {
+        return true;
+    };
+    // Alt stands in for the Ctrl the terminal cannot send with digits
+    let action = game_state.key_map.action(chord).or_else(|| {
+        if chord.alt {
+            game_state.key_map.action(KeyChord { ctrl: true, alt: false, ..chord })
+        } else {
+            None
+        }
+    });
+    match action {
+        Some(action) => perform(game_state, action),
+        None => true,
+    }
+}
+
+fn perform(game_state: &mut GameState, action: InputAction) -> bool {
+    // While asking whether to quit, quitting again confirms and any other
+    // action cancels
+    if game_state.quit_requested {
+        game_state.quit_requested = false;
+        return action != InputAction::Quit;
+    }
+
+    match action {
+        InputAction::Quit => {
+            game_state.quit_requested = true;
+        }
+        InputAction::NewPuzzle => {
+            game_state.new_puzzle();
+        }
+        InputAction::NextTheme => {
+            game_state.next_theme();
+        }
+        InputAction::ToggleCandidateMode => {
+            game_state.candidate_button_pressed = !game_state.candidate_button_pressed;
+        }
+        InputAction::ToggleColorMode => {
+            game_state.color_mode = !game_state.color_mode;
+        }
+        InputAction::ClearColors => {
+            game_state.edit(|game_state| game_state.clear_colors());
+        }
+        InputAction::PlaceDigit(val) | InputAction::CornerMark(val) | InputAction::CenterMark(val)
+            if game_state.color_mode =>
+        {
+            game_state.paint_selection((val - 1) as usize);
+        }
+        InputAction::Clear if game_state.color_mode => {
+            game_state.unpaint_selection();
+        }
+        InputAction::PlaceDigit(val) if game_state.candidate_button_pressed => {
+            game_state.toggle_candidate(val, MarkKind::Corner);
+        }
+        InputAction::PlaceDigit(val) => {
+            game_state.place(val);
+        }
+        InputAction::CornerMark(val) => {
+            game_state.toggle_candidate(val, MarkKind::Corner);
+        }
+        InputAction::CenterMark(val) => {
+            game_state.toggle_candidate(val, MarkKind::Center);
+        }
+        InputAction::Clear => {
+            game_state.clear();
+        }
+        InputAction::Hint => {
+            game_state.hint();
+        }
+        InputAction::Move(rows, cols) => {
+            game_state.move_selection(rows, cols);
+        }
+        InputAction::ExtendSelection(rows, cols) => {
+            game_state.extend_selection(rows, cols);
+        }
+        InputAction::Undo => {
+            game_state.undo();
+        }
+        InputAction::Redo => {
+            game_state.redo();
+        }
+        // The font size is up to the terminal
+        InputAction::ZoomIn | InputAction::ZoomOut => {}
+        // Library puzzles are picked in the window, the terminal carries on
+        // with them through the save file
+        InputAction::OpenLibrary => {}
+        // There is no calendar here, the key goes straight to today's puzzle
+        InputAction::OpenCalendar => {
+            game_state.start_daily(daily::today());
+        }
+    }
+    true
+}
+
+// Run the clock and take the events of the last pass. Character cells leave
+// no room for motion beyond the completion wave, but a new puzzle, which may
+// only arrive from the worker here, gets the cursor in its corner.
+pub fn advance(game_state: &mut GameState, dt: Duration) {
+    game_state.tick(dt);
+    if game_state.take_events().contains(&GameEvent::NewPuzzle) {
+        game_state.select(0, 0);
+    }
+}
+
+// Raw mode and the alternate screen for as long as it lives, so the
+// terminal is handed back usable even when the game stops with an error
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        queue!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        io::stdout().flush()?;
+        Ok(Terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = queue!(io::stdout(), style::ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = io::stdout().flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn write_screen(out: &mut impl Write, screen: &Screen) -> io::Result<()> {
+    let rgb = |color: Color| style::Color::Rgb { r: color.r, g: color.g, b: color.b };
+    let mut last: Option<TermCell> = None;
+    for y in 0..screen.height {
+        queue!(out, cursor::MoveTo(0, y as u16))?;
+        for x in 0..screen.width {
+            let cell = screen.cell(x, y);
+            // Only send what changed since the previous character
+            if last.map(|last| (last.fg, last.bg, last.bold, last.underline)) != Some((cell.fg, cell.bg, cell.bold, cell.underline)) {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reset),
+                    SetForegroundColor(rgb(cell.fg)),
+                    SetBackgroundColor(rgb(cell.bg))
+                )?;
+                if cell.bold {
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                }
+                if cell.underline {
+                    queue!(out, SetAttribute(Attribute::Underlined))?;
+                }
+                last = Some(cell);
+            }
+            queue!(out, Print(cell.ch))?;
+        }
+    }
+    out.flush()
+}
+
+// Ask the terminal to put text on the clipboard (OSC 52), which also works
+// over SSH. Terminals that do not support it ignore the request.
+fn copy_to_clipboard(out: &mut impl Write, text: &str) -> io::Result<()> {
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    out.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+pub fn run() -> Result<(), String> {
+    let mut game_state = GameState::new();
+    game_state.themes.extend(Theme::load_dir(&config::resolve_path("assets/themes")));
+    game_state.apply_config(Config::load());
+    game_state.records = Records::load();
+    game_state.library = Library::load();
+    game_state.puzzle_queue = Some(PuzzleQueue::start());
+    if let Some(saved) = SavedGame::load() {
+        if let Err(e) = saved.restore(&mut game_state) {
+            eprintln!("Ignoring saved game: {}", e);
+        }
+    }
+    if game_state.board_initialized {
+        game_state.select(0, 0);
+    }
+
+    let terminal = Terminal::enter().map_err(|e| e.to_string())?;
+    let mut out = io::stdout();
+    let mut last_tick = Instant::now();
+    loop {
+        let (width, height) = terminal::size().map_err(|e| e.to_string())?;
+        let screen = draw(&game_state, width as usize, height as usize);
+        write_screen(&mut out, &screen).map_err(|e| e.to_string())?;
+
+        // Wake up every second to keep the clock going, more often while
+        // the completion animation runs or a puzzle is on its way
+        let timeout = if game_state.is_animating() { Duration::from_millis(50) } else { Duration::from_secs(1) };
+        if event::poll(timeout).map_err(|e| e.to_string())? {
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                if key.kind != KeyEventKind::Release && !handle_key(&mut game_state, key) {
+                    break;
+                }
+                if let Some(text) = game_state.clipboard.take() {
+                    copy_to_clipboard(&mut out, &text).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        let now = Instant::now();
+        advance(&mut game_state, now - last_tick);
+        last_tick = now;
+    }
+    drop(terminal);
+
+    if let Err(e) = game_state.records.save() {
+        eprintln!("Could not save the records: {}", e);
+    }
+    game_state.shelve_library_puzzle();
+    if let Err(e) = game_state.library.progress.save() {
+        eprintln!("Could not save the library progress: {}", e);
+    }
+    SavedGame::store(&game_state).map_err(|e| format!("Could not save the game: {}", e))
+}
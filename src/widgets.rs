@@ -0,0 +1,351 @@
+use std::time::Duration;
+
+use chrono::Datelike;
+use sdl2::rect::Rect;
+
+use crate::board_generator::{BoardDifficulty, BoardLayout};
+use crate::daily;
+use crate::game::{self, GameState, GuiData};
+use crate::library::PuzzleStatus;
+use crate::theme::PALETTE_SIZE;
+
+// What happens when a button is clicked
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ButtonAction {
+    NewPuzzle,
+    ToggleCandidate,
+    Solve,
+    CycleLayout,
+    SetDifficulty(BoardDifficulty),
+    ToggleVariantMenu,
+    ToggleAntiKnight,
+    ToggleAntiKing,
+    ToggleNonConsecutive,
+    OpenSettings,
+    CycleDefaultDifficulty,
+    CycleTheme,
+    CycleWindowSize,
+    ToggleAnimations,
+    OpenLibrary,
+    SaveSettings,
+    CloseSettings,
+    CycleLibraryFilter,
+    // Turn this many pages of the library list
+    LibraryPage(i32),
+    // Index into the library's puzzles
+    OpenLibraryPuzzle(usize),
+    CloseLibrary,
+    OpenCalendar,
+    // Show the month this many months away
+    CalendarMonth(i32),
+    // Play the daily puzzle of the date with this number of days from the
+    // Common Era, see chrono::Datelike::num_days_from_ce
+    OpenDaily(i32),
+    CloseCalendar,
+    ConfirmQuit,
+    CancelQuit,
+    ToggleColorMode,
+    // Paint with the palette color of this index
+    PickColor(usize),
+    ClearColors,
+    // Leave the completion screen to set up the next puzzle
+    CloseSummary,
+    Share,
+}
+
+// A clickable button. The same definition is used to draw the button and to
+// hit test clicks against it, so both always agree on where it is.
+pub struct Button {
+    pub rect: Rect,
+    pub label: String,
+    // Highlighted, e.g. the selected difficulty or an enabled toggle
+    pub active: bool,
+    pub action: ButtonAction,
+}
+
+impl Button {
+    fn new(rect: Rect, label: &str, active: bool, action: ButtonAction) -> Button {
+        Button { rect, label: label.to_string(), active, action }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.rect.contains_point((x, y))
+    }
+
+    // The area the button covers on screen, including its border
+    pub fn outer_rect(&self) -> Rect {
+        Rect::new(
+            self.rect.x() - 4,
+            self.rect.y() - 4,
+            self.rect.width() + 8,
+            self.rect.height() + 8
+        )
+    }
+}
+
+// Every button currently on screen, in drawing order. The variant menu
+// entries come last since they are drawn on top of everything else. While
+// the settings screen or the quit dialog is open only its rows can be clicked,
+// and a finished puzzle only offers what to do next.
+pub fn layout_buttons(game_state: &GameState, gui_data: &GuiData) -> Vec<Button> {
+    if game_state.quit_requested {
+        return quit_buttons(gui_data);
+    }
+    if game_state.settings_open {
+        return settings_buttons(game_state, gui_data);
+    }
+    if game_state.library_open {
+        return library_buttons(game_state, gui_data);
+    }
+    if game_state.calendar_open {
+        return calendar_buttons(game_state, gui_data);
+    }
+    if let Some(completion) = &game_state.completion {
+        let share = if completion.shared { "Copied" } else { "Share" };
+        let entries = [
+            ("New puzzle", ButtonAction::CloseSummary),
+            ("Same difficulty", ButtonAction::NewPuzzle),
+            (share, ButtonAction::Share),
+        ];
+        return entries
+            .into_iter()
+            .zip(gui_data.completion_buttons())
+            .map(|((label, action), rect)| Button::new(rect, label, false, action))
+            .collect();
+    }
+
+    let layout_name = match game_state.layout {
+        BoardLayout::Classic => "Classic",
+        BoardLayout::Jigsaw => "Jigsaw",
+        BoardLayout::Samurai => "Samurai",
+    };
+
+    let level_1 = [
+        ("New Puzzle", false, ButtonAction::NewPuzzle),
+        ("Candidate", game_state.candidate_button_pressed, ButtonAction::ToggleCandidate),
+        ("Solve", false, ButtonAction::Solve),
+        (layout_name, game_state.layout != BoardLayout::Classic, ButtonAction::CycleLayout),
+        ("Colors", game_state.color_mode, ButtonAction::ToggleColorMode),
+        ("Settings", false, ButtonAction::OpenSettings),
+    ];
+
+    let difficulties = [
+        ("Beginner", BoardDifficulty::Beginner),
+        ("Easy", BoardDifficulty::Easy),
+        ("Medium", BoardDifficulty::Medium),
+        ("Hard", BoardDifficulty::Hard),
+        ("Expert", BoardDifficulty::Expert),
+    ];
+    // While coloring, the second row holds the palette instead of the difficulties
+    let level_2: Vec<(String, bool, ButtonAction)> = if game_state.color_mode {
+        (0..PALETTE_SIZE)
+            .map(|index| {
+                ((index + 1).to_string(), game_state.paint_color == index, ButtonAction::PickColor(index))
+            })
+            .chain([("Clear colors".to_string(), false, ButtonAction::ClearColors)])
+            .collect()
+    } else {
+        difficulties
+            .iter()
+            .map(|&(label, difficulty)| {
+                (label.to_string(), game_state.difficulty == difficulty, ButtonAction::SetDifficulty(difficulty))
+            })
+            .chain([("Variants".to_string(), game_state.variant_menu_open, ButtonAction::ToggleVariantMenu)])
+            .collect()
+    };
+
+    let mut buttons: Vec<Button> = level_1
+        .into_iter()
+        .zip(&gui_data.buttons_level_1)
+        .map(|((label, active, action), rect)| Button::new(*rect, label, active, action))
+        .chain(
+            level_2
+                .into_iter()
+                .zip(&gui_data.buttons_level_2)
+                .map(|((label, active, action), rect)| Button::new(*rect, &label, active, action))
+        )
+        .collect();
+
+    if game_state.variant_menu_open {
+        let constraints = game_state.selected_constraints;
+        let entries = [
+            ("Anti-Knight", constraints.anti_knight, ButtonAction::ToggleAntiKnight),
+            ("Anti-King", constraints.anti_king, ButtonAction::ToggleAntiKing),
+            ("Non-Consecutive", constraints.non_consecutive, ButtonAction::ToggleNonConsecutive),
+        ];
+        // Entries are spaced so their borders line up
+        for (index, (label, active, action)) in entries.into_iter().enumerate() {
+            let rect = Rect::new(
+                gui_data.menu_x,
+                gui_data.menu_y + (index as i32) * (gui_data.menu_entry_height as i32),
+                gui_data.menu_width,
+                gui_data.menu_entry_height - 8
+            );
+            buttons.push(Button::new(rect, label, active, action));
+        }
+    }
+
+    buttons
+}
+
+// Number of rows on the settings screen
+pub const SETTINGS_ENTRIES: usize = 8;
+
+fn settings_buttons(game_state: &GameState, gui_data: &GuiData) -> Vec<Button> {
+    let config = &game_state.config;
+    let entries = [
+        (format!("Difficulty: {:?}", config.difficulty), ButtonAction::CycleDefaultDifficulty),
+        (format!("Theme: {}", game_state.theme().name), ButtonAction::CycleTheme),
+        (format!("Window: {}% (next start)", config.window.height_percent), ButtonAction::CycleWindowSize),
+        (format!("Motion: {}", if config.animations { "On" } else { "Off" }), ButtonAction::ToggleAnimations),
+        ("Puzzle library".to_string(), ButtonAction::OpenLibrary),
+        ("Daily puzzles".to_string(), ButtonAction::OpenCalendar),
+        ("Save".to_string(), ButtonAction::SaveSettings),
+        ("Close".to_string(), ButtonAction::CloseSettings),
+    ];
+
+    entries
+        .into_iter()
+        .zip(gui_data.settings_entries(SETTINGS_ENTRIES))
+        .map(|((label, action), rect)| Button::new(rect, &label, false, action))
+        .collect()
+}
+
+// Puzzles listed on one page of the library, and the rows of its screen:
+// the filter, the puzzles, and a row to turn pages or leave
+pub const LIBRARY_PAGE: usize = 8;
+pub const LIBRARY_ENTRIES: usize = LIBRARY_PAGE + 2;
+
+fn library_buttons(game_state: &GameState, gui_data: &GuiData) -> Vec<Button> {
+    let library = &game_state.library;
+    let rows = gui_data.settings_entries(LIBRARY_ENTRIES);
+    let filtered = library.filtered(game_state.library_filter);
+    let pages = filtered.len().div_ceil(LIBRARY_PAGE).max(1);
+
+    let filter = match game_state.library_filter {
+        Some(difficulty) => format!("{:?}", difficulty),
+        None => "All".to_string(),
+    };
+    let label = format!("Show: {}  (page {} of {})", filter, game_state.library_page + 1, pages);
+    let mut buttons = vec![Button::new(rows[0], &label, false, ButtonAction::CycleLibraryFilter)];
+
+    let page = filtered.iter().skip(game_state.library_page * LIBRARY_PAGE).take(LIBRARY_PAGE);
+    for (&index, rect) in page.zip(&rows[1..]) {
+        let puzzle = &library.puzzles[index];
+        let key = puzzle.key();
+        let status = match library.progress.puzzles.get(&key) {
+            Some(progress) if progress.status == PuzzleStatus::Solved => match progress.best_time {
+                Some(seconds) => format!("solved in {}", game::format_time(Duration::from_secs(seconds))),
+                None => "solved".to_string(),
+            },
+            Some(progress) if progress.status == PuzzleStatus::InProgress => "in progress".to_string(),
+            _ => "new".to_string(),
+        };
+        let label = format!("{} #{}, {:?}, {}", puzzle.collection, puzzle.number, puzzle.difficulty, status);
+        let current = game_state.library_key.as_ref() == Some(&key);
+        buttons.push(Button::new(*rect, &label, current, ButtonAction::OpenLibraryPuzzle(index)));
+    }
+
+    // The last row is split in three
+    let last = rows[LIBRARY_ENTRIES - 1];
+    let width = last.width() / 3;
+    let entries = [
+        ("Previous", ButtonAction::LibraryPage(-1)),
+        ("Next", ButtonAction::LibraryPage(1)),
+        ("Close", ButtonAction::CloseLibrary),
+    ];
+    for (index, (label, action)) in entries.into_iter().enumerate() {
+        let rect = Rect::new(last.x() + (index as i32) * (width as i32), last.y(), width - 4, last.height());
+        buttons.push(Button::new(rect, label, false, action));
+    }
+    buttons
+}
+
+// Rows of the calendar of daily puzzles: the month, up to six weeks, and a
+// row to play today's puzzle or leave
+pub const CALENDAR_ENTRIES: usize = 8;
+
+fn calendar_buttons(game_state: &GameState, gui_data: &GuiData) -> Vec<Button> {
+    let rows = gui_data.settings_entries(CALENDAR_ENTRIES);
+    let records = &game_state.records;
+    let today = daily::today();
+    let month = game_state.calendar_month;
+
+    let label = format!(
+        "{}, streak {} (best {})",
+        month.format("%B %Y"),
+        records.current_streak(today),
+        records.longest_streak()
+    );
+    let header = [
+        ("<".to_string(), ButtonAction::CalendarMonth(-1)),
+        (label, ButtonAction::CalendarMonth(0)),
+        (">".to_string(), ButtonAction::CalendarMonth(1)),
+    ];
+    // The month takes the middle of the first row, the arrows a seventh each
+    let first = rows[0];
+    let cell = first.width() / 7;
+    let mut x = first.x();
+    let mut buttons = Vec::new();
+    for (label, action) in header {
+        let width = if action == ButtonAction::CalendarMonth(0) { cell * 5 } else { cell };
+        buttons.push(Button::new(Rect::new(x, first.y(), width - 4, first.height()), &label, false, action));
+        x += width as i32;
+    }
+
+    // Days with a solved puzzle are highlighted, later ones have no puzzle yet
+    for (week, row) in daily::weeks(month).iter().zip(&rows[1..CALENDAR_ENTRIES - 1]) {
+        for (weekday, date) in week.iter().enumerate() {
+            let Some(date) = date.filter(|&date| date <= today) else {
+                continue;
+            };
+            let label = if date == today { format!("[{}]", date.day()) } else { date.day().to_string() };
+            let solved = records.daily_time(date).is_some();
+            let rect = Rect::new(row.x() + (weekday as i32) * (cell as i32), row.y(), cell - 4, row.height());
+            buttons.push(Button::new(rect, &label, solved, ButtonAction::OpenDaily(date.num_days_from_ce())));
+        }
+    }
+
+    let last = rows[CALENDAR_ENTRIES - 1];
+    let width = last.width() / 2;
+    let entries = [
+        ("Today's puzzle", ButtonAction::OpenDaily(today.num_days_from_ce())),
+        ("Close", ButtonAction::CloseCalendar),
+    ];
+    for (index, (label, action)) in entries.into_iter().enumerate() {
+        let rect = Rect::new(last.x() + (index as i32) * (width as i32), last.y(), width - 4, last.height());
+        buttons.push(Button::new(rect, label, false, action));
+    }
+    buttons
+}
+
+// Number of rows on the quit dialog
+pub const QUIT_ENTRIES: usize = 2;
+
+fn quit_buttons(gui_data: &GuiData) -> Vec<Button> {
+    let rects = gui_data.settings_entries(QUIT_ENTRIES);
+    vec![
+        Button::new(rects[0], "Quit game", false, ButtonAction::ConfirmQuit),
+        Button::new(rects[1], "Keep playing", false, ButtonAction::CancelQuit),
+    ]
+}
+
+// Number of rows of the panel shown over the board, if any
+pub fn panel_entries(game_state: &GameState) -> Option<usize> {
+    if game_state.quit_requested {
+        Some(QUIT_ENTRIES)
+    } else if game_state.settings_open {
+        Some(SETTINGS_ENTRIES)
+    } else if game_state.library_open {
+        Some(LIBRARY_ENTRIES)
+    } else if game_state.calendar_open {
+        Some(CALENDAR_ENTRIES)
+    } else {
+        None
+    }
+}
+
+// The topmost button under the given point
+pub fn button_at(buttons: &[Button], x: i32, y: i32) -> Option<&Button> {
+    buttons.iter().rev().find(|button| button.contains(x, y))
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use sudoku::animation::{wave, Animations, Easing, Tween};
+use sudoku::game::{GameEvent, GameState};
+
+#[test]
+fn test_tweens_run_from_start_to_end() {
+    let mut tween = Tween::new(2.0, 4.0, Duration::from_millis(100), Easing::EaseOut);
+    assert_eq!(tween.value(), 2.0);
+    tween.advance(Duration::from_millis(50));
+    assert!(tween.value() > 3.0 && tween.value() < 4.0);
+    tween.advance(Duration::from_secs(1));
+    assert!(tween.is_done());
+    assert_eq!(tween.value(), 4.0);
+
+    assert_eq!(Easing::Overshoot.apply(1.0), 1.0);
+    assert!(Easing::Overshoot.apply(0.7) > 1.0);
+    assert_eq!(wave(0.0, 0.5, 0.4), 0.0);
+    assert!(wave(0.5, 0.5, 0.4) > 0.99);
+}
+
+#[test]
+fn test_events_start_cell_animations() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let mut animations = Animations::default();
+
+    animations.start(&GameEvent::DigitPlaced { row: 1, col: 2, digit: 5 }, &game_state);
+    animations.start(&GameEvent::Conflict { row: 3, col: 4, digit: 5 }, &game_state);
+    assert!(animations.is_running());
+    assert!(animations.scale(1, 2) < 1.0);
+    assert_eq!(animations.scale(0, 0), 1.0);
+    assert_eq!(animations.flash(3, 4), 1.0);
+
+    animations.advance(Duration::from_secs(1));
+    assert!(!animations.is_running());
+    assert_eq!(animations.scale(1, 2), 1.0);
+    assert_eq!(animations.shake(3, 4), 0.0);
+}
+
+#[test]
+fn test_filling_a_row_sweeps_it() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    game_state.solve();
+    let mut animations = Animations::default();
+
+    animations.start(&GameEvent::HintPlaced { row: 0, col: 4 }, &game_state);
+    animations.advance(Duration::from_millis(100));
+    // The sweep starts at the first cell of the row and column
+    assert!(animations.sweep(0, 0) > 0.0);
+    assert_eq!(animations.sweep(0, 8), 0.0);
+    assert_eq!(animations.sweep(5, 5), 0.0);
+}
+
+#[test]
+fn test_cursor_slides_unless_motion_is_off() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let mut animations = Animations::default();
+
+    game_state.select(0, 0);
+    animations.follow_cursor(&game_state);
+    assert_eq!(animations.cursor(), None);
+    game_state.select(0, 2);
+    animations.follow_cursor(&game_state);
+    assert_eq!(animations.cursor(), Some((0.0, 0.0)));
+    animations.advance(Duration::from_millis(40));
+    let (_, col) = animations.cursor().unwrap();
+    assert!(col > 0.0 && col < 2.0);
+    animations.advance(Duration::from_secs(1));
+    assert_eq!(animations.cursor(), None);
+
+    game_state.config.animations = false;
+    game_state.select(4, 4);
+    animations.follow_cursor(&game_state);
+    animations.start(&GameEvent::DigitPlaced { row: 4, col: 4, digit: 1 }, &game_state);
+    assert!(!animations.is_running());
+}
+
+#[test]
+fn test_solving_sends_a_wave_over_the_board() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    game_state.select(0, 0);
+    game_state.take_events();
+
+    let events = game_state.solve();
+    assert_eq!(game_state.take_events(), events);
+    let mut animations = Animations::default();
+    for event in &events {
+        animations.start(event, &game_state);
+    }
+    animations.advance(Duration::from_millis(150));
+    assert!(animations.sweep(0, 0) > 0.0);
+    assert_eq!(animations.sweep(8, 8), 0.0);
+}
@@ -53,4 +53,15 @@ fn test_is_valid_true() {
     ];
 
     assert_eq!(board_generator.is_valid(&board, 0, 0, 1), true);
-}
\ No newline at end of file
+}
+#[test]
+fn test_get_square_follows_jigsaw_regions() {
+    let difficulty = board_generator::BoardDifficulty::Easy;
+    let mut board_generator = board_generator::BoardGenerator::new_with_layout(difficulty, board_generator::BoardLayout::Jigsaw);
+    board_generator.generate_sudoku();
+    let solution = board_generator.get_solved_board().unwrap();
+
+    let mut digits: Vec<i32> = board_generator.get_square(&solution, 4, 4).into_iter().flatten().flatten().collect();
+    digits.sort();
+    assert_eq!(digits, (1..=9).collect::<Vec<i32>>());
+}
@@ -0,0 +1,31 @@
+use sudoku::board_generator::BoardDifficulty;
+use sudoku::config::Config;
+
+#[test]
+fn test_missing_settings_use_defaults() {
+    let config = Config::parse("difficulty = \"expert\"\n\n[window]\nheight_percent = 70\n").unwrap();
+
+    assert_eq!(config.difficulty, BoardDifficulty::Expert);
+    assert_eq!(config.window.height_percent, 70);
+    assert_eq!(config.window.max_width_percent, 90);
+    assert_eq!(config.fonts, Config::default().fonts);
+    assert!(config.keys.is_empty());
+}
+
+#[test]
+fn test_invalid_config_is_rejected() {
+    assert!(Config::parse("difficulty = \"impossible\"").is_err());
+}
+
+#[test]
+fn test_saved_config_reads_back() {
+    let path = std::env::temp_dir().join(format!("sudoku-config-test-{}", std::process::id())).join("config.toml");
+    let mut config = Config { theme: "Dark".to_string(), ..Config::default() };
+    config.keys.insert("next_theme".to_string(), vec!["F2".to_string()]);
+
+    config.save_to(&path).unwrap();
+    let loaded = Config::from_file(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    assert_eq!(loaded.unwrap(), config);
+}
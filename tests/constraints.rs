@@ -0,0 +1,40 @@
+use sudoku::board_generator::{BoardDifficulty, BoardGenerator, BoardLayout};
+use sudoku::constraints::Constraints;
+
+#[test]
+fn test_anti_knight_peers() {
+    let constraints = Constraints { anti_knight: true, ..Constraints::default() };
+    let mut peers = constraints.peers(0, 0, 9);
+    peers.sort();
+
+    assert_eq!(peers, vec![(1, 2), (2, 1)]);
+}
+
+#[test]
+fn test_non_consecutive_allows() {
+    let constraints = Constraints { non_consecutive: true, ..Constraints::default() };
+    let mut board: Vec<Vec<Option<i32>>> = vec![vec![None; 9]; 9];
+    board[4][5] = Some(6);
+
+    assert!(!constraints.allows(&board, 4, 4, 5));
+    assert!(!constraints.allows(&board, 4, 4, 7));
+    assert!(constraints.allows(&board, 4, 4, 4));
+    // diagonal neighbours are not affected
+    assert!(constraints.allows(&board, 3, 4, 4));
+}
+
+#[test]
+fn test_generated_solution_respects_constraints() {
+    let constraints = Constraints { anti_knight: true, anti_king: true, non_consecutive: true };
+    let mut board_generator = BoardGenerator::new_with_variant(BoardDifficulty::Easy, BoardLayout::Jigsaw, constraints);
+    board_generator.generate_sudoku();
+    let solution = board_generator.get_solved_board().unwrap();
+
+    for row in 0..9 {
+        for col in 0..9 {
+            let val = solution[row][col].unwrap();
+            assert!(constraints.allows(&solution, row, col, val));
+            assert!(constraints.peers(row, col, 9).iter().all(|&(r, c)| solution[r][c] != Some(val)));
+        }
+    }
+}
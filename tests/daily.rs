@@ -0,0 +1,98 @@
+use chrono::NaiveDate;
+
+use sudoku::board_generator::{BoardDifficulty, BoardLayout};
+use sudoku::daily;
+use sudoku::game::GameState;
+use sudoku::save::{Records, SavedGame};
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[test]
+fn test_a_date_always_gives_the_same_puzzle() {
+    let mut first = GameState::new();
+    let mut second = GameState::new();
+    first.start_daily(date(2026, 10, 19));
+    second.new_puzzle();
+    second.start_daily(date(2026, 10, 19));
+
+    assert_eq!(daily::seed(date(2026, 10, 19)), 20261019);
+    assert_eq!(first.initial_board, second.initial_board);
+    assert_eq!(first.solved_board, second.solved_board);
+    assert_eq!(first.difficulty, BoardDifficulty::Easy);
+    assert_eq!(first.layout, BoardLayout::Classic);
+
+    second.start_daily(date(2026, 10, 20));
+    assert_ne!(first.initial_board, second.initial_board);
+}
+
+#[test]
+fn test_difficulty_follows_the_weekday() {
+    assert_eq!(daily::difficulty(date(2026, 10, 19)), BoardDifficulty::Easy);
+    assert_eq!(daily::difficulty(date(2026, 10, 21)), BoardDifficulty::Medium);
+    assert_eq!(daily::difficulty(date(2026, 10, 24)), BoardDifficulty::Hard);
+    assert_eq!(daily::difficulty(date(2026, 10, 25)), BoardDifficulty::Expert);
+}
+
+#[test]
+fn test_calendar_weeks_start_on_monday() {
+    // February 2026 starts on a Sunday
+    let weeks = daily::weeks(date(2026, 2, 14));
+    assert_eq!(weeks.len(), 5);
+    assert_eq!(weeks[0][6], Some(date(2026, 2, 1)));
+    assert_eq!(weeks[0][0], None);
+    assert_eq!(weeks[4][5], Some(date(2026, 2, 28)));
+    assert_eq!(daily::add_months(date(2026, 1, 31), -2), date(2025, 11, 1));
+}
+
+#[test]
+fn test_streaks_count_days_in_a_row() {
+    let mut records = Records::default();
+    for day in [1, 2, 3, 5, 6] {
+        records.record_daily(date(2026, 3, day), std::time::Duration::from_secs(300), date(2026, 3, day));
+    }
+
+    assert_eq!(records.longest_streak(), 3);
+    assert_eq!(records.current_streak(date(2026, 3, 6)), 2);
+    // Today's puzzle is still to be played
+    assert_eq!(records.current_streak(date(2026, 3, 7)), 2);
+    assert_eq!(records.current_streak(date(2026, 3, 8)), 0);
+}
+
+#[test]
+fn test_catching_up_on_a_day_does_not_extend_a_streak() {
+    let mut records = Records::default();
+    let time = std::time::Duration::from_secs(300);
+    records.record_daily(date(2026, 3, 2), time, date(2026, 3, 2));
+    records.record_daily(date(2026, 3, 1), time, date(2026, 3, 2));
+
+    assert!(records.daily_time(date(2026, 3, 1)).is_some());
+    assert_eq!(records.current_streak(date(2026, 3, 2)), 1);
+    assert_eq!(records.longest_streak(), 1);
+}
+
+#[test]
+fn test_solving_the_daily_records_it() {
+    let mut game_state = GameState::new();
+    let today = daily::today();
+    game_state.start_daily(today);
+    let saved = SavedGame::from_state(&game_state).unwrap();
+    assert_eq!(saved.daily, Some(daily::key(today)));
+
+    game_state.edit(|game_state| {
+        game_state.board = game_state.solved_board.clone();
+        game_state.board[0][0] = None;
+    });
+    game_state.select(0, 0);
+    game_state.hint();
+
+    assert!(game_state.records.daily_time(today).is_some());
+    let summary = game_state.completion.as_ref().unwrap().summary();
+    assert!(summary[2].starts_with(&format!("Daily {}", daily::key(today))));
+    assert_eq!(summary[3], "Daily streak: 1 day");
+
+    let mut restored = GameState::new();
+    saved.restore(&mut restored).unwrap();
+    assert_eq!(restored.daily, Some(today));
+}
@@ -0,0 +1,360 @@
+use std::time::Duration;
+
+use sudoku::constraints::Constraints;
+use sudoku::events::Events;
+use sudoku::game::{DigitSource, GameEvent, GameState, GuiData, MarkKind};
+use sudoku::input::InputEvent;
+use sudoku::regions::Regions;
+
+#[test]
+fn test_only_changes_need_a_redraw() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+
+    let still = InputEvent::MouseMove { x: 1, y: 1, dx: 1, dy: 0, left: false, right: false };
+    assert!(Events::process_input(&mut game_state, &gui_data, [still]));
+    assert!(!game_state.needs_redraw);
+
+    assert!(Events::process_input(&mut game_state, &gui_data, [InputEvent::Other]));
+    assert!(game_state.needs_redraw);
+}
+
+#[test]
+fn test_digit_sources_track_who_filled_a_cell() {
+    let mut game_state = GameState::new();
+    game_state.generate_new_board();
+    game_state.board_initialized = true;
+
+    let empty: Vec<(usize, usize)> = (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .filter(|&(row, col)| game_state.board[row][col].is_none())
+        .collect();
+    let given = (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .find(|&(row, col)| game_state.initial_board[row][col].is_some())
+        .unwrap();
+
+    let (hint_row, hint_col) = empty[0];
+    game_state.selected_square = Some((hint_row as i32, hint_col as i32));
+    assert!(game_state.place_hint());
+    assert!(!game_state.place_hint());
+
+    game_state.solve();
+    let (solved_row, solved_col) = empty[1];
+    assert_eq!(game_state.digit_source(given.0, given.1), Some(DigitSource::Given));
+    assert_eq!(game_state.digit_source(hint_row, hint_col), Some(DigitSource::Hint));
+    assert_eq!(game_state.digit_source(solved_row, solved_col), Some(DigitSource::Solver));
+}
+
+#[test]
+fn test_undo_and_redo_edits() {
+    let mut game_state = GameState::new();
+    game_state.generate_new_board();
+    game_state.board_initialized = true;
+    let (row, col) = empty_cell(&game_state);
+
+    game_state.edit(|game_state| game_state.board[row][col] = Some(5));
+    // Edits that change nothing are not recorded
+    game_state.edit(|_| {});
+
+    assert!(game_state.undo());
+    assert_eq!(game_state.board[row][col], None);
+    assert!(!game_state.undo());
+    assert!(game_state.redo());
+    assert_eq!(game_state.board[row][col], Some(5));
+    assert!(!game_state.redo());
+}
+
+#[test]
+fn test_move_selection_stops_at_the_edge() {
+    let mut game_state = GameState::new();
+    game_state.generate_new_board();
+    game_state.board_initialized = true;
+
+    game_state.move_selection(0, 1);
+    assert_eq!(game_state.selected_square, Some((0, 0)));
+    game_state.move_selection(-1, 0);
+    assert_eq!(game_state.selected_square, Some((0, 0)));
+    game_state.move_selection(1, 0);
+    game_state.move_selection(0, 1);
+    assert_eq!(game_state.selected_square, Some((1, 1)));
+}
+
+fn empty_cell(game_state: &GameState) -> (usize, usize) {
+    (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .find(|&(row, col)| game_state.board[row][col].is_none())
+        .unwrap()
+}
+
+#[test]
+fn test_painting_a_cell_twice_removes_the_color() {
+    let mut game_state = GameState::new();
+    game_state.generate_new_board();
+
+    game_state.edit(|game_state| game_state.paint_cell(4, 4, 2));
+    assert_eq!(game_state.cell_colors[4][4], Some(2));
+    game_state.edit(|game_state| game_state.paint_cell(4, 4, 2));
+    assert_eq!(game_state.cell_colors[4][4], None);
+
+    game_state.paint_cell(0, 0, 1);
+    game_state.edit(|game_state| game_state.clear_colors());
+    assert_eq!(game_state.cell_colors[0][0], None);
+    assert!(game_state.undo());
+    assert_eq!(game_state.cell_colors[0][0], Some(1));
+}
+
+#[test]
+fn test_selection_grows_and_skips_givens_for_edits() {
+    let mut game_state = GameState::new();
+    game_state.generate_new_board();
+    game_state.board_initialized = true;
+
+    game_state.select(0, 0);
+    game_state.extend_selection(0, 1);
+    game_state.extend_selection(0, 1);
+    game_state.toggle_selected(8, 8);
+    assert_eq!(game_state.selected_cells, vec![(0, 0), (0, 1), (0, 2), (8, 8)]);
+    assert_eq!(game_state.selected_square, Some((8, 8)));
+
+    game_state.toggle_selected(8, 8);
+    assert_eq!(game_state.selected_square, Some((0, 2)));
+    let editable = game_state.editable_cells();
+    assert!(editable.iter().all(|&(row, col)| game_state.initial_board[row][col].is_none()));
+
+    game_state.move_selection(1, 0);
+    assert_eq!(game_state.selected_cells, vec![(1, 2)]);
+}
+
+#[test]
+fn test_placing_digits_reports_conflicts() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let (row, col) = empty_cell(&game_state);
+    let answer = game_state.solved_board[row][col].unwrap();
+    // On an otherwise full board every other digit breaks a rule
+    game_state.solve();
+    game_state.edit(|game_state| game_state.board[row][col] = None);
+
+    game_state.select(row as i32, col as i32);
+    let wrong = answer % 9 + 1;
+    assert_eq!(
+        game_state.place(wrong),
+        vec![GameEvent::DigitPlaced { row, col, digit: wrong }, GameEvent::Conflict { row, col, digit: wrong }]
+    );
+    let other = wrong % 9 + 1;
+    game_state.place(other);
+    assert_eq!(game_state.invalid_positions, vec![(row as i32, col as i32, other)]);
+
+    assert_eq!(game_state.clear(), vec![GameEvent::CellCleared { row, col }]);
+    assert!(game_state.invalid_positions.is_empty());
+}
+
+#[test]
+fn test_a_hint_can_reveal_a_conflict() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    // Only the first row and column are left to fill, the first cell gets
+    // the answer of the second and clashes with nothing yet
+    game_state.edit(|game_state| {
+        game_state.initial_board = vec![vec![None; 9]; 9];
+        game_state.board = game_state.solved_board.clone();
+        for index in 0..9 {
+            game_state.board[0][index] = None;
+            game_state.board[index][0] = None;
+        }
+    });
+    let digit = game_state.solved_board[0][1].unwrap();
+    game_state.select(0, 0);
+    game_state.place(digit);
+    assert!(game_state.invalid_positions.is_empty());
+
+    game_state.select(0, 1);
+    game_state.hint();
+    // Marked like a restored game would, but not the player's mistake
+    assert_eq!(game_state.invalid_positions, vec![(0, 0, digit)]);
+    assert_eq!(game_state.invalid_positions, game_state.find_conflicts());
+    assert_eq!(game_state.mistakes, 0);
+}
+
+#[test]
+fn test_conflicts_follow_the_rules_of_the_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.board_initialized = true;
+    game_state.board[2][2] = Some(5);
+    game_state.board[3][4] = Some(5);
+    assert!(game_state.find_conflicts().is_empty());
+
+    game_state.set_rules(Regions::classic(), Constraints { anti_knight: true, ..Constraints::default() });
+    assert_eq!(game_state.find_conflicts(), vec![(2, 2, 5), (3, 4, 5)]);
+}
+
+#[test]
+fn test_candidates_toggle_and_clear() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let (row, col) = empty_cell(&game_state);
+    game_state.select(row as i32, col as i32);
+
+    assert_eq!(game_state.toggle_candidate(3, MarkKind::Center), vec![GameEvent::MarksChanged { row, col }]);
+    game_state.toggle_candidate(5, MarkKind::Corner);
+    assert_eq!(game_state.center_marks[row][col], Some(vec![3]));
+    game_state.toggle_candidate(5, MarkKind::Corner);
+    assert_eq!(game_state.corner_marks[row][col], Some(vec![]));
+
+    assert_eq!(game_state.clear(), vec![GameEvent::MarksChanged { row, col }]);
+    assert_eq!(game_state.center_marks[row][col], None);
+    assert!(game_state.clear().is_empty());
+}
+
+#[test]
+fn test_filling_the_last_cell_completes_the_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let (row, col) = empty_cell(&game_state);
+    let answer = game_state.solved_board[row][col].unwrap();
+    game_state.edit(|game_state| {
+        game_state.board = game_state.solved_board.clone();
+        game_state.board[row][col] = None;
+    });
+
+    game_state.select(row as i32, col as i32);
+    let events = game_state.place(answer);
+    assert_eq!(events.last(), Some(&GameEvent::Completed));
+    assert!(game_state.puzzle_solved);
+    assert!(!game_state.board_initialized);
+
+    // The solved grid stays on screen with a summary until it is closed
+    assert_eq!(game_state.board, game_state.solved_board);
+    let completion = game_state.completion.clone().unwrap();
+    assert_eq!(completion.previous_best, None);
+    assert_eq!(completion.summary()[3], "First one solved!");
+    assert!(game_state.is_animating());
+    assert!(game_state.share().unwrap().starts_with("Sudoku: Solved in"));
+
+    game_state.close_completion();
+    assert!(game_state.completion.is_none());
+    assert!(game_state.board.iter().flatten().all(|cell| cell.is_none()));
+}
+
+#[test]
+fn test_a_solved_board_is_not_the_players() {
+    let mut game_state = GameState::new();
+    game_state.start_daily(sudoku::daily::today());
+    let (row, col) = empty_cell(&game_state);
+    let answer = game_state.solved_board[row][col].unwrap();
+    assert_eq!(game_state.solve(), vec![GameEvent::Solved]);
+    assert!(game_state.solver_filled());
+
+    // Entering one of the solver's digits again leaves the rest of them
+    game_state.select(row as i32, col as i32);
+    game_state.clear();
+    assert!(!game_state.place(answer).contains(&GameEvent::Completed));
+    assert!(game_state.completion.is_none());
+    assert_eq!(game_state.records.best_time(game_state.layout, game_state.difficulty), None);
+    assert_eq!(game_state.records.daily_time(sudoku::daily::today()), None);
+}
+
+#[test]
+fn test_completion_counts_mistakes_hints_and_records() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let (row, col) = empty_cell(&game_state);
+    let answer = game_state.solved_board[row][col].unwrap();
+    game_state.edit(|game_state| {
+        game_state.board = game_state.solved_board.clone();
+        game_state.board[row][col] = None;
+    });
+
+    // Any digit other than the answer clashes with the full board around it
+    game_state.select(row as i32, col as i32);
+    game_state.place(answer % 9 + 1);
+    game_state.clear();
+    game_state.records.record(game_state.layout, game_state.difficulty, Duration::from_secs(90));
+    game_state.tick(Duration::from_secs(30));
+    assert_eq!(game_state.hint().last(), Some(&GameEvent::Completed));
+
+    let completion = game_state.completion.as_ref().unwrap();
+    assert_eq!((completion.mistakes, completion.hints), (1, 1));
+    assert!(completion.is_new_best());
+    assert_eq!(completion.summary()[..2], ["Solved in 0:30", "1 mistake, 1 hint"]);
+    assert_eq!(game_state.records.best_time(game_state.layout, game_state.difficulty), Some(Duration::from_secs(30)));
+
+    // The wave has passed once the animation is over
+    game_state.tick(Duration::from_secs(5));
+    assert!(!game_state.is_animating());
+    assert_eq!(game_state.completion_glow(4, 4), 0.0);
+}
+
+#[test]
+fn test_any_valid_full_board_completes_the_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    // An imported puzzle without givens, solved with 1 and 2 swapped
+    let swapped: Vec<Vec<Option<i32>>> = game_state
+        .solved_board
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.map(|digit| [2, 1, 3, 4, 5, 6, 7, 8, 9][digit as usize - 1])).collect())
+        .collect();
+    game_state.initial_board = vec![vec![None; 9]; 9];
+    game_state.edit(|game_state| {
+        game_state.board = swapped.clone();
+        game_state.board[8][8] = None;
+    });
+
+    game_state.select(8, 8);
+    let events = game_state.place(swapped[8][8].unwrap());
+    assert_eq!(events[events.len() - 2..], [GameEvent::AlternateSolution, GameEvent::Completed]);
+    let completion = game_state.completion.as_ref().unwrap();
+    assert!(completion.alternate_solution);
+    assert!(completion.summary()[2].ends_with("a solution of your own"));
+}
+
+#[test]
+fn test_redo_can_complete_the_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let (row, col) = empty_cell(&game_state);
+    let answer = game_state.solved_board[row][col].unwrap();
+    game_state.edit(|game_state| {
+        game_state.board = game_state.solved_board.clone();
+        game_state.board[row][col] = None;
+    });
+    game_state.edit(|game_state| game_state.board[row][col] = Some(answer));
+    game_state.undo();
+    assert!(game_state.completion.is_none());
+
+    assert!(game_state.redo());
+    assert!(game_state.completion.is_some());
+    assert_eq!(game_state.take_events(), [GameEvent::NewPuzzle, GameEvent::Completed]);
+}
+
+#[test]
+fn test_redoing_solve_does_not_complete_the_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    game_state.solve();
+    assert!(game_state.undo());
+    assert!(!game_state.solver_filled());
+
+    assert!(game_state.redo());
+    assert!(game_state.solver_filled());
+    assert!(game_state.completion.is_none());
+    assert!(game_state.board_initialized);
+    assert_eq!(game_state.records.best_time(game_state.layout, game_state.difficulty), None);
+    assert_eq!(game_state.take_events(), [GameEvent::NewPuzzle, GameEvent::Solved]);
+}
+
+#[test]
+fn test_clock_only_runs_during_a_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.tick(Duration::from_secs(5));
+    assert_eq!(game_state.elapsed, Duration::ZERO);
+
+    game_state.new_puzzle();
+    game_state.tick(Duration::from_secs(2));
+    game_state.tick(Duration::from_secs(3));
+    assert_eq!(game_state.elapsed, Duration::from_secs(5));
+    assert_eq!(game_state.new_puzzle(), vec![GameEvent::NewPuzzle]);
+    assert_eq!(game_state.elapsed, Duration::ZERO);
+}
@@ -0,0 +1,29 @@
+use sudoku::game::GuiData;
+
+#[test]
+fn test_portrait_layout_puts_buttons_below_board() {
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    let board_bottom = gui_data.board_y + gui_data.board_pixels() as i32;
+
+    assert!(!gui_data.landscape);
+    assert!(gui_data.buttons_level_1.iter().all(|rect| rect.y() > board_bottom));
+    assert!(gui_data.buttons_level_2.iter().all(|rect| rect.bottom() <= 1000));
+}
+
+#[test]
+fn test_landscape_layout_puts_buttons_right_of_board() {
+    let gui_data = GuiData::new(1600, 900, 1.0);
+    let board_right = gui_data.board_x + gui_data.board_pixels() as i32;
+
+    assert!(gui_data.landscape);
+    assert!(gui_data.buttons_level_1.iter().chain(&gui_data.buttons_level_2).all(|rect| rect.x() > board_right));
+    assert!(gui_data.buttons_level_2.iter().all(|rect| rect.right() <= 1600 && rect.bottom() <= 900));
+}
+
+#[test]
+fn test_high_dpi_scales_mouse_coordinates() {
+    let gui_data = GuiData::new(1600, 2000, 2.0);
+
+    assert_eq!(gui_data.to_pixels(100, 250), (200, 500));
+    assert!(gui_data.font_size_numbers > GuiData::new(800, 1000, 1.0).font_size_numbers);
+}
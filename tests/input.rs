@@ -0,0 +1,48 @@
+use sudoku::input::{InputAction, Key, KeyChord};
+
+#[test]
+fn test_action_names_roundtrip() {
+    for (action, name) in InputAction::all() {
+        assert_eq!(InputAction::from_name(&name), Some(action));
+    }
+    let named = [
+        ("place_1", InputAction::PlaceDigit(1)),
+        ("place_9", InputAction::PlaceDigit(9)),
+        ("clear", InputAction::Clear),
+        ("extend_down", InputAction::ExtendSelection(1, 0)),
+        ("undo", InputAction::Undo),
+        ("redo", InputAction::Redo),
+        ("hint", InputAction::Hint),
+        ("color_mode", InputAction::ToggleColorMode),
+        ("clear_colors", InputAction::ClearColors),
+        ("new_puzzle", InputAction::NewPuzzle),
+        ("library", InputAction::OpenLibrary),
+        ("daily", InputAction::OpenCalendar),
+        ("quit", InputAction::Quit),
+    ];
+    for (name, action) in named {
+        assert_eq!(InputAction::from_name(name), Some(action));
+        assert!(InputAction::all().contains(&(action, name.to_string())));
+    }
+}
+
+#[test]
+fn test_key_chords_are_written_as_they_are_read() {
+    for spec in ["Z", "Ctrl+Shift+Z", "Keypad +", "Alt+Keypad Enter", "PageUp", "Left GUI", "\\", "F12"] {
+        assert_eq!(KeyChord::parse(spec).unwrap().to_string(), spec);
+    }
+    assert_eq!(KeyChord::parse("cmd+page up").unwrap().to_string(), "Ctrl+PageUp");
+    assert_eq!(Key::from_name("keypad 1"), Some(Key::Kp1));
+    assert!(KeyChord::parse("Ctrl+Hyper").is_err());
+}
+
+#[test]
+fn test_actions_are_looked_up_by_name() {
+    assert_eq!(InputAction::from_name("corner_5"), Some(InputAction::CornerMark(5)));
+    assert_eq!(InputAction::from_name("center_5"), Some(InputAction::CenterMark(5)));
+    assert_eq!(InputAction::from_name("move_left"), Some(InputAction::Move(0, -1)));
+    assert_eq!(InputAction::from_name("teleport"), None);
+    for (action, _) in InputAction::all() {
+        assert!(!action.default_keys().is_empty());
+    }
+}
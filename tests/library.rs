@@ -0,0 +1,101 @@
+use std::path::Path;
+use std::time::Duration;
+
+use sudoku::board_generator::{BoardDifficulty, BoardLayout};
+use sudoku::game::{GameEvent, GameState};
+use sudoku::library::{Library, LibraryProgress, PuzzleStatus};
+
+const PUZZLE: &str = "400030610060719004093640070357160000240000001609000035000400807000902043020001500";
+
+#[test]
+fn test_collections_are_parsed_and_graded() {
+    let puzzles = Library::parse("mine", &format!("{}\n\n{}\n", PUZZLE, PUZZLE.replace('0', "."))).unwrap();
+    assert_eq!(puzzles.len(), 2);
+    assert_eq!(puzzles[1].key(), "mine/3");
+    assert_eq!(puzzles[0].difficulty, BoardDifficulty::Easy);
+    assert_eq!(puzzles[0].givens[0][0], Some(4));
+    assert_eq!(puzzles[0].givens[0][1], None);
+
+    assert_eq!(Library::parse("Hard", PUZZLE).unwrap()[0].difficulty, BoardDifficulty::Hard);
+    assert!(Library::parse("mine", &PUZZLE[1..]).is_err());
+    assert!(Library::parse("mine", &PUZZLE.replace('4', "x")).is_err());
+}
+
+#[test]
+fn test_bundled_collections_load() {
+    let library = Library { puzzles: Library::load_dir(Path::new("assets/puzzles")), ..Library::default() };
+    assert_eq!(library.puzzles.len(), 30);
+    assert_eq!(library.filtered(None).len(), 30);
+    assert_eq!(library.filtered(Some(BoardDifficulty::Expert)).len(), 6);
+}
+
+#[test]
+fn test_imports_never_replace_a_collection() {
+    let root = std::env::temp_dir().join(format!("sudoku-import-{}", std::process::id()));
+    let (source, dir) = (root.join("source"), root.join("puzzles"));
+    std::fs::create_dir_all(&source).unwrap();
+    for name in ["mine.sdm", "Easy.sdm"] {
+        std::fs::write(source.join(name), PUZZLE).unwrap();
+    }
+
+    assert_eq!(Library::import_to(&source.join("mine.sdm"), &dir), Ok(1));
+    let again = Library::import_to(&source.join("mine.sdm"), &dir);
+    let bundled = Library::import_to(&source.join("Easy.sdm"), &dir);
+    let imported = Library::load_dir(&dir);
+    std::fs::remove_dir_all(&root).unwrap();
+    assert!(again.is_err());
+    assert!(bundled.is_err());
+    assert_eq!(imported.len(), 1);
+
+    let mut puzzle = Library::parse("easy", PUZZLE).unwrap().remove(0);
+    assert_eq!(puzzle.key(), "easy/1");
+    puzzle.imported = true;
+    assert_eq!(puzzle.key(), "imported/easy/1");
+}
+
+#[test]
+fn test_library_puzzles_resume_and_get_solved() {
+    let mut game_state = GameState::new();
+    game_state.library.puzzles = Library::parse("mine", PUZZLE).unwrap();
+    game_state.layout = BoardLayout::Samurai;
+    assert_eq!(game_state.open_library_puzzle(0).unwrap(), vec![GameEvent::NewPuzzle]);
+    assert_eq!(game_state.layout, BoardLayout::Classic);
+    assert_eq!(game_state.library_key.as_deref(), Some("mine/1"));
+
+    // Leaving for a generated puzzle keeps the board for later
+    game_state.select(0, 1);
+    let digit = game_state.solved_board[0][1].unwrap();
+    game_state.place(digit);
+    game_state.new_puzzle();
+    assert_eq!(game_state.library_key, None);
+    assert_eq!(game_state.library.progress.status("mine/1"), PuzzleStatus::InProgress);
+
+    game_state.open_library_puzzle(0).unwrap();
+    assert_eq!(game_state.board[0][1], Some(digit));
+    game_state.edit(|game_state| game_state.board = game_state.solved_board.clone());
+    game_state.tick(Duration::from_secs(75));
+    game_state.select(0, 1);
+    game_state.clear();
+    game_state.place(digit);
+    let progress = &game_state.library.progress.puzzles["mine/1"];
+    assert_eq!(progress.status, PuzzleStatus::Solved);
+    assert_eq!(progress.best_time, Some(75));
+    assert_eq!(progress.game, None);
+}
+
+#[test]
+fn test_progress_reads_back() {
+    let mut game_state = GameState::new();
+    game_state.library.puzzles = Library::parse("mine", PUZZLE).unwrap();
+    game_state.open_library_puzzle(0).unwrap();
+    game_state.select(0, 1);
+    game_state.toggle_candidate(5, sudoku::game::MarkKind::Corner);
+    game_state.shelve_library_puzzle();
+    game_state.library.progress.mark_solved("other/2", Duration::from_secs(30));
+
+    let path = std::env::temp_dir().join(format!("sudoku-library-{}.toml", std::process::id()));
+    game_state.library.progress.save_to(&path).unwrap();
+    let loaded = LibraryProgress::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), game_state.library.progress);
+}
@@ -0,0 +1,109 @@
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use sudoku::board_generator::{BoardDifficulty, BoardGenerator, BoardLayout};
+use sudoku::constraints::Constraints;
+use sudoku::game::{GameEvent, GameState};
+use sudoku::puzzle_queue::{PuzzleKind, PuzzleQueue};
+
+fn kind(difficulty: BoardDifficulty) -> PuzzleKind {
+    PuzzleKind { difficulty, layout: BoardLayout::Classic, constraints: Constraints::default() }
+}
+
+// Tick the game until the worker's puzzle has started
+fn wait_for_puzzle(game_state: &mut GameState) -> Vec<GameEvent> {
+    let deadline = Instant::now() + Duration::from_secs(120);
+    while Instant::now() < deadline {
+        let events = game_state.tick(Duration::from_millis(10));
+        if !events.is_empty() {
+            return events;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("no puzzle was generated");
+}
+
+#[test]
+fn test_cancelled_generation_gives_up() {
+    let mut board_generator = BoardGenerator::new(BoardDifficulty::Expert);
+    assert_eq!(board_generator.generate_sudoku_cancellable(&AtomicBool::new(true)), None);
+}
+
+#[test]
+fn test_queue_fills_with_the_wanted_kind() {
+    let queue = PuzzleQueue::start();
+    assert_eq!(queue.ready(kind(BoardDifficulty::Easy)), 0);
+    queue.want(kind(BoardDifficulty::Easy));
+
+    let deadline = Instant::now() + Duration::from_secs(120);
+    while queue.ready(kind(BoardDifficulty::Easy)) == 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let puzzle = queue.take(kind(BoardDifficulty::Easy)).unwrap();
+    for (row, cells) in puzzle.board.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            assert!(cell.is_none() || *cell == puzzle.solution[row][col]);
+        }
+    }
+    assert!(queue.take(kind(BoardDifficulty::Hard)).is_none());
+}
+
+// Sleep until the queue has a puzzle of the kind
+fn wait_for_ready(queue: &PuzzleQueue, kind: PuzzleKind) {
+    let deadline = Instant::now() + Duration::from_secs(120);
+    while queue.ready(kind) == 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(queue.ready(kind) > 0);
+}
+
+#[test]
+fn test_queue_keeps_only_the_neighbouring_difficulties() {
+    let queue = PuzzleQueue::start();
+    queue.want(kind(BoardDifficulty::Beginner));
+    wait_for_ready(&queue, kind(BoardDifficulty::Easy));
+
+    queue.want(kind(BoardDifficulty::Expert));
+    assert_eq!(queue.ready(kind(BoardDifficulty::Beginner)), 0);
+    assert_eq!(queue.ready(kind(BoardDifficulty::Easy)), 0);
+    wait_for_ready(&queue, kind(BoardDifficulty::Hard));
+    assert_eq!(queue.ready(kind(BoardDifficulty::Medium)), 0);
+}
+
+#[test]
+fn test_new_puzzle_waits_for_the_worker() {
+    let mut game_state = GameState::new();
+    game_state.puzzle_queue = Some(PuzzleQueue::start());
+    game_state.difficulty = BoardDifficulty::Expert;
+
+    assert!(game_state.new_puzzle().is_empty());
+    assert!(game_state.loading.is_some());
+    assert!(!game_state.board_initialized);
+    assert!(game_state.is_animating());
+
+    // Picking another difficulty while waiting gets a puzzle of that one
+    game_state.difficulty = BoardDifficulty::Beginner;
+    assert_eq!(wait_for_puzzle(&mut game_state), [GameEvent::NewPuzzle]);
+    assert!(game_state.loading.is_none());
+    assert!(game_state.board_initialized);
+    let givens = game_state.initial_board.iter().flatten().filter(|cell| cell.is_some()).count();
+    assert!(givens > 45);
+}
+
+#[test]
+fn test_the_daily_puzzle_waits_for_the_worker() {
+    let date = chrono::NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+    let mut expected = GameState::new();
+    expected.start_daily(date);
+
+    let mut game_state = GameState::new();
+    game_state.puzzle_queue = Some(PuzzleQueue::start());
+    game_state.tick(Duration::ZERO);
+    assert!(game_state.start_daily(date).is_empty());
+    assert!(game_state.loading.is_some());
+
+    assert_eq!(wait_for_puzzle(&mut game_state), [GameEvent::NewPuzzle]);
+    assert_eq!(game_state.daily, Some(date));
+    assert_eq!(game_state.initial_board, expected.initial_board);
+    assert_eq!(game_state.solved_board, expected.solved_board);
+}
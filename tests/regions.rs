@@ -0,0 +1,61 @@
+use sudoku::board_generator::{BoardDifficulty, BoardGenerator};
+use sudoku::regions::Regions;
+
+#[test]
+fn test_classic_regions() {
+    let regions = Regions::classic();
+
+    assert_eq!(regions.region_of(0, 0), Some(0));
+    assert_eq!(regions.region_of(4, 4), Some(4));
+    assert_eq!(regions.region_of(8, 6), Some(8));
+    assert!(regions.is_boundary((2, 2), (2, 3)));
+    assert!(!regions.is_boundary((1, 1), (2, 2)));
+}
+
+#[test]
+fn test_random_jigsaw_is_valid() {
+    let mut board_generator = BoardGenerator::new(BoardDifficulty::Beginner);
+    board_generator.generate_sudoku();
+    let solution = board_generator.get_solved_board().unwrap();
+
+    let regions = Regions::random_jigsaw(&solution, &mut rand::thread_rng());
+    let map: Vec<Vec<usize>> = (0..9)
+        .map(|row| (0..9).map(|col| regions.region_of(row, col).unwrap()).collect())
+        .collect();
+
+    assert!(Regions::from_map(map).is_ok());
+    // every region still holds each digit once
+    for region in 0..9 {
+        let mut digits: Vec<i32> = regions
+            .cells(region)
+            .iter()
+            .map(|&(row, col)| solution[row][col].unwrap())
+            .collect();
+        digits.sort();
+        assert_eq!(digits, (1..=9).collect::<Vec<i32>>());
+    }
+}
+
+#[test]
+fn test_from_map_rejects_disconnected_region() {
+    let mut map: Vec<Vec<usize>> = (0..9)
+        .map(|row| (0..9).map(|col| (row / 3) * 3 + col / 3).collect())
+        .collect();
+    // swap two cells between opposite corner boxes
+    map[0][0] = 8;
+    map[8][8] = 0;
+
+    assert!(Regions::from_map(map).is_err());
+}
+
+#[test]
+fn test_samurai_shares_corner_boxes() {
+    let regions = Regions::samurai();
+
+    assert_eq!(regions.size(), 21);
+    assert!(!regions.is_active(0, 10));
+    assert!(regions.is_active(10, 10));
+    // the top-left grid's bottom-right box is the centre grid's top-left box
+    assert_eq!(regions.region_of(8, 8), regions.region_of(6, 6));
+    assert_eq!(regions.cells(regions.region_of(8, 8).unwrap()).len(), 9);
+}
@@ -0,0 +1,171 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sudoku::config::FontConfig;
+use sudoku::fonts;
+use sudoku::game::{DigitSource, GameState, GuiData};
+use sudoku::glyph_cache::GlyphCache;
+use sudoku::screen_renderer::ScreenRenderer;
+
+const WIDTH: u32 = 480;
+const HEIGHT: u32 = 600;
+
+// Font rasterizers differ slightly between FreeType versions, so a few
+// pixels may be off by a little
+const CHANNEL_TOLERANCE: u8 = 24;
+const MAX_DIFFERENT_PIXELS: f64 = 0.005;
+
+#[test]
+fn test_start_screen_matches_golden() {
+    assert_matches_golden("start_screen", &GameState::new());
+}
+
+#[test]
+fn test_puzzle_matches_golden() {
+    assert_matches_golden("puzzle", &puzzle_state());
+}
+
+#[test]
+fn test_dark_puzzle_matches_golden() {
+    let mut game_state = puzzle_state();
+    assert!(game_state.select_theme("Dark"));
+    assert_matches_golden("puzzle_dark", &game_state);
+}
+
+#[test]
+fn test_settings_screen_matches_golden() {
+    let mut game_state = puzzle_state();
+    game_state.settings_open = true;
+    assert_matches_golden("settings", &game_state);
+}
+
+#[test]
+fn test_completion_screen_matches_golden() {
+    let mut game_state = puzzle_state();
+    game_state.edit(|game_state| {
+        game_state.board = game_state.solved_board.clone();
+        game_state.board[0][1] = None;
+    });
+    game_state.select(0, 1);
+    game_state.hint();
+    game_state.tick(Duration::from_secs(2));
+    assert_matches_golden("solved", &game_state);
+}
+
+// A fixed puzzle showing every kind of cell: givens, player entries, a hint,
+// a conflict, pencil marks, colors and a selection
+fn puzzle_state() -> GameState {
+    let mut game_state = GameState::new();
+    let solution: Vec<Vec<Option<i32>>> = (0..9)
+        .map(|row| (0..9).map(|col| Some((row * 3 + row / 3 + col) % 9 + 1)).collect())
+        .collect();
+    let givens: Vec<Vec<Option<i32>>> = solution
+        .iter()
+        .enumerate()
+        .map(|(row, values)| {
+            values.iter().enumerate().map(|(col, &value)| value.filter(|_| (row * 7 + col * 5) % 3 == 0)).collect()
+        })
+        .collect();
+
+    game_state.solved_board = solution.clone();
+    game_state.initial_board = givens.clone();
+    game_state.board = givens;
+    game_state.board_initialized = true;
+
+    let empty: Vec<(usize, usize)> = (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .filter(|&(row, col)| game_state.board[row][col].is_none())
+        .collect();
+
+    let (row, col) = empty[0];
+    game_state.board[row][col] = solution[row][col];
+    let (row, col) = empty[1];
+    game_state.board[row][col] = solution[row][col];
+    game_state.entry_sources[row][col] = DigitSource::Hint;
+    let (row, col) = empty[2];
+    let wrong = solution[row][col].unwrap() % 9 + 1;
+    game_state.board[row][col] = Some(wrong);
+    game_state.invalid_positions.push((row as i32, col as i32, wrong));
+
+    let (row, col) = empty[3];
+    game_state.corner_marks[row][col] = Some(vec![1, 4, 7]);
+    game_state.center_marks[row][col] = Some(vec![2, 5]);
+    let (row, col) = empty[4];
+    game_state.cell_colors[row][col] = Some(1);
+    game_state.center_marks[row][col] = Some(vec![3, 6, 8, 9]);
+
+    let (row, col) = empty[5];
+    game_state.select(row as i32, col as i32);
+    game_state
+}
+
+// Render the state without a window and compare it with
+// tests/golden/<name>.png. Set SUDOKU_BLESS=1 to write the golden images,
+// for a new test or after an intended change to the look.
+fn assert_matches_golden(name: &str, game_state: &GameState) {
+    let mut renderer = ScreenRenderer::headless(WIDTH, HEIGHT).unwrap();
+    let gui_data = GuiData::new(WIDTH, HEIGHT, 1.0);
+    let ttf_context = sdl2::ttf::init().unwrap();
+    let fonts = fonts::load_fonts(&ttf_context, &FontConfig::default(), &gui_data).unwrap();
+    let texture_creator = renderer.canvas.texture_creator();
+    let mut glyphs = GlyphCache::new(&texture_creator);
+
+    renderer.draw(game_state, fonts.iter().collect(), &gui_data, &mut glyphs).unwrap();
+    let pixels = renderer.pixels().unwrap();
+
+    let golden = Path::new("tests/golden").join(format!("{}.png", name));
+    if std::env::var_os("SUDOKU_BLESS").is_some() {
+        write_png(&golden, &pixels);
+        return;
+    }
+    if !golden.exists() {
+        let actual = actual_path(&golden);
+        write_png(&actual, &pixels);
+        panic!("{} is missing, see {} and bless it with SUDOKU_BLESS=1", golden.display(), actual.display());
+    }
+
+    let expected = read_png(&golden);
+    let different = pixels
+        .chunks(3)
+        .zip(expected.chunks(3))
+        .filter(|(actual, expected)| actual.iter().zip(expected.iter()).any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE))
+        .count();
+    let fraction = different as f64 / f64::from(WIDTH * HEIGHT);
+
+    if expected.len() != pixels.len() || fraction > MAX_DIFFERENT_PIXELS {
+        let actual = actual_path(&golden);
+        write_png(&actual, &pixels);
+        panic!(
+            "{} differs from {} in {:.2}% of its pixels, see {}",
+            name,
+            golden.display(),
+            fraction * 100.0,
+            actual.display()
+        );
+    }
+}
+
+fn actual_path(golden: &Path) -> PathBuf {
+    golden.with_extension("actual.png")
+}
+
+fn write_png(path: &Path, pixels: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+}
+
+fn read_png(path: &Path) -> Vec<u8> {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height, info.color_type), (WIDTH, HEIGHT, png::ColorType::Rgb));
+    pixels.truncate(info.buffer_size());
+    pixels
+}
@@ -0,0 +1,141 @@
+use sudoku::game::{DigitSource, GameState, GuiData};
+use sudoku::replay::replay;
+
+fn new_game() -> (GameState, GuiData) {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    assert!(replay(&mut game_state, &gui_data, "button New Puzzle").unwrap());
+    (game_state, gui_data)
+}
+
+fn empty_cells(game_state: &GameState) -> Vec<(usize, usize)> {
+    (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .filter(|&(row, col)| game_state.board[row][col].is_none())
+        .collect()
+}
+
+#[test]
+fn test_clicks_select_cells_and_keys_fill_them() {
+    let (mut game_state, gui_data) = new_game();
+    assert!(game_state.board_initialized);
+    let (row, col) = empty_cells(&game_state)[0];
+
+    let script = format!("click_cell {} {}\nkey 5\n", row, col);
+    assert!(replay(&mut game_state, &gui_data, &script).unwrap());
+    assert_eq!(game_state.selected_square, Some((row as i32, col as i32)));
+    assert_eq!(game_state.board[row][col], Some(5));
+    assert_eq!(game_state.digit_source(row, col), Some(DigitSource::Player));
+
+    replay(&mut game_state, &gui_data, "key Backspace").unwrap();
+    assert_eq!(game_state.board[row][col], None);
+    replay(&mut game_state, &gui_data, "key Ctrl+Z").unwrap();
+    assert_eq!(game_state.board[row][col], Some(5));
+}
+
+#[test]
+fn test_pencil_marks_from_keys_and_candidate_mode() {
+    let (mut game_state, gui_data) = new_game();
+    let (row, col) = empty_cells(&game_state)[0];
+
+    let script = format!(
+        "click_cell {} {}\n\
+         key Shift+1   # corner mark\n\
+         key Ctrl+2    # center mark\n\
+         button Candidate\n\
+         key 3\n",
+        row, col
+    );
+    replay(&mut game_state, &gui_data, &script).unwrap();
+    assert!(game_state.candidate_button_pressed);
+    assert_eq!(game_state.corner_marks[row][col], Some(vec![1, 3]));
+    assert_eq!(game_state.center_marks[row][col], Some(vec![2]));
+    assert_eq!(game_state.board[row][col], None);
+}
+
+#[test]
+fn test_ctrl_click_and_drag_select_several_cells() {
+    let (mut game_state, gui_data) = new_game();
+    let empty = empty_cells(&game_state);
+    let (first, second) = (empty[0], empty[1]);
+
+    let script = format!(
+        "click_cell {} {}\npress Ctrl+Left Ctrl\nclick_cell {} {}\nrelease Left Ctrl\nkey Ctrl+7\n",
+        first.0, first.1, second.0, second.1
+    );
+    replay(&mut game_state, &gui_data, &script).unwrap();
+    assert_eq!(game_state.selected_cells.len(), 2);
+    assert_eq!(game_state.center_marks[first.0][first.1], Some(vec![7]));
+    assert_eq!(game_state.center_marks[second.0][second.1], Some(vec![7]));
+
+    // A plain click starts a new selection that grows while dragging
+    let (start, end) = (empty[2], empty[3]);
+    let from = gui_data.cell_rect(&game_state, start.0, start.1).center();
+    let to = gui_data.cell_rect(&game_state, end.0, end.1).center();
+    let script = format!(
+        "down {} {}\ndrag {} {}\nup {} {}\n",
+        from.x(), from.y(), to.x(), to.y(), to.x(), to.y()
+    );
+    replay(&mut game_state, &gui_data, &script).unwrap();
+    assert_eq!(game_state.selected_cells, vec![(start.0 as i32, start.1 as i32), (end.0 as i32, end.1 as i32)]);
+    assert!(!game_state.drag_selecting);
+}
+
+#[test]
+fn test_filling_the_last_cell_completes_the_puzzle() {
+    let (mut game_state, gui_data) = new_game();
+    let empty = empty_cells(&game_state);
+    let (row, col) = empty[0];
+    for &(r, c) in &empty[1..] {
+        game_state.board[r][c] = game_state.solved_board[r][c];
+    }
+
+    let script = format!("click_cell {} {}\nkey {}\n", row, col, game_state.solved_board[row][col].unwrap());
+    replay(&mut game_state, &gui_data, &script).unwrap();
+    assert!(game_state.puzzle_solved);
+    assert!(!game_state.board_initialized);
+}
+
+#[test]
+fn test_escape_asks_before_quitting() {
+    let (mut game_state, gui_data) = new_game();
+
+    assert!(replay(&mut game_state, &gui_data, "key Escape").unwrap());
+    assert!(game_state.quit_requested);
+    assert!(replay(&mut game_state, &gui_data, "button Keep playing").unwrap());
+    assert!(!game_state.quit_requested);
+    assert!(!replay(&mut game_state, &gui_data, "key Escape\nkey Escape").unwrap());
+}
+
+#[test]
+fn test_bad_scripts_report_the_line() {
+    let (mut game_state, gui_data) = new_game();
+
+    assert!(replay(&mut game_state, &gui_data, "# setup\njump 1 2").unwrap_err().contains("line 2"));
+    assert!(replay(&mut game_state, &gui_data, "click 1").is_err());
+    assert!(replay(&mut game_state, &gui_data, "button Nonexistent").is_err());
+}
+
+#[test]
+fn test_keys_leave_the_board_alone_under_a_panel() {
+    let (mut game_state, gui_data) = new_game();
+    let (row, col) = empty_cells(&game_state)[0];
+    replay(&mut game_state, &gui_data, &format!("click_cell {} {}", row, col)).unwrap();
+
+    let panels: [fn(&mut GameState); 3] = [
+        |game_state| game_state.settings_open = true,
+        |game_state| game_state.library_open = true,
+        |game_state| game_state.calendar_open = true,
+    ];
+    for open in panels {
+        open(&mut game_state);
+        replay(&mut game_state, &gui_data, "key 5\nkey H\nkey Right").unwrap();
+        assert_eq!(game_state.board[row][col], None);
+        assert_eq!(game_state.selected_square, Some((row as i32, col as i32)));
+        // Escape closes the panel and the keys reach the board again
+        replay(&mut game_state, &gui_data, "key Escape").unwrap();
+        assert!(!game_state.quit_requested);
+    }
+    replay(&mut game_state, &gui_data, "key 5").unwrap();
+    assert_eq!(game_state.board[row][col], Some(5));
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use sudoku::board_generator::{BoardDifficulty, BoardLayout};
+use sudoku::game::{DigitSource, GameState};
+use sudoku::save::{Records, SavedGame};
+
+#[test]
+fn test_saved_game_restores_the_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let empty: Vec<(usize, usize)> = (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .filter(|&(row, col)| game_state.board[row][col].is_none())
+        .collect();
+
+    let (row, col) = empty[0];
+    let wrong = game_state.solved_board[row][col].unwrap() % 9 + 1;
+    game_state.select(row as i32, col as i32);
+    game_state.place(wrong);
+    let (hint_row, hint_col) = empty[1];
+    game_state.select(hint_row as i32, hint_col as i32);
+    game_state.hint();
+    let (row, col) = empty[2];
+    game_state.corner_marks[row][col] = Some(vec![1, 4]);
+    game_state.center_marks[row][col] = Some(vec![7]);
+    game_state.cell_colors[row][col] = Some(3);
+
+    let saved = SavedGame::from_state(&game_state).unwrap();
+    let text = toml::to_string_pretty(&saved).unwrap();
+    let mut restored = GameState::new();
+    SavedGame::parse(&text).unwrap().restore(&mut restored).unwrap();
+
+    assert!(restored.board_initialized);
+    assert_eq!(restored.board, game_state.board);
+    assert_eq!(restored.initial_board, game_state.initial_board);
+    assert_eq!(restored.solved_board, game_state.solved_board);
+    assert_eq!(restored.corner_marks[row][col], Some(vec![1, 4]));
+    assert_eq!(restored.center_marks[row][col], Some(vec![7]));
+    assert_eq!(restored.cell_colors[row][col], Some(3));
+    assert_eq!(restored.digit_source(hint_row, hint_col), Some(DigitSource::Hint));
+    let mut conflicts = restored.invalid_positions.clone();
+    let mut expected = game_state.invalid_positions.clone();
+    conflicts.sort();
+    expected.sort();
+    assert_eq!(conflicts, expected);
+}
+
+#[test]
+fn test_jigsaw_regions_are_saved() {
+    let mut game_state = GameState::new();
+    game_state.layout = BoardLayout::Jigsaw;
+    game_state.new_puzzle();
+
+    let mut restored = GameState::new();
+    SavedGame::from_state(&game_state).unwrap().restore(&mut restored).unwrap();
+    assert_eq!(restored.layout, BoardLayout::Jigsaw);
+    assert_eq!(restored.regions, game_state.regions);
+}
+
+#[test]
+fn test_nothing_is_saved_without_a_puzzle() {
+    assert_eq!(SavedGame::from_state(&GameState::new()), None);
+}
+
+#[test]
+fn test_broken_save_is_rejected() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let mut saved = SavedGame::from_state(&game_state).unwrap();
+    saved.board.pop();
+    assert!(saved.restore(&mut GameState::new()).is_err());
+    assert!(SavedGame::parse("difficulty = \"medium\"").is_err());
+
+    // A bad cell is only found after the boards, which are left alone
+    let mut saved = SavedGame::from_state(&game_state).unwrap();
+    saved.board[0] = ".........".to_string();
+    saved.layout = BoardLayout::Classic;
+    saved.cells.push(sudoku::save::SavedCell {
+        row: 12,
+        col: 0,
+        corner: Vec::new(),
+        center: Vec::new(),
+        color: Some(1),
+        source: None,
+    });
+    let mut other = GameState::new();
+    other.new_puzzle();
+    let board = other.board.clone();
+    assert!(saved.restore(&mut other).is_err());
+    assert_eq!(other.board, board);
+    assert!(other.board_initialized);
+}
+
+#[test]
+fn test_records_keep_the_best_time() {
+    let mut records = Records::default();
+    let (layout, difficulty) = (BoardLayout::Classic, BoardDifficulty::Medium);
+    assert_eq!(records.record(layout, difficulty, Duration::from_secs(300)), None);
+    assert_eq!(records.record(layout, difficulty, Duration::from_secs(400)), Some(Duration::from_secs(300)));
+    assert_eq!(records.best_time(layout, difficulty), Some(Duration::from_secs(300)));
+    assert_eq!(records.best_time(BoardLayout::Samurai, difficulty), None);
+
+    let path = std::env::temp_dir().join(format!("sudoku-records-{}.toml", std::process::id()));
+    records.save_to(&path).unwrap();
+    assert_eq!(Records::from_file(&path).unwrap(), records);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,45 @@
+use sudoku::board_generator::{BoardDifficulty, BoardGenerator, BoardLayout};
+use sudoku::solver::Solver;
+
+#[test]
+fn test_count_solutions_empty_board() {
+    let solver = Solver::new(sudoku::regions::Regions::classic());
+    let board = vec![vec![None; 9]; 9];
+
+    assert_eq!(solver.count_solutions(&board, 2), 2);
+}
+
+#[test]
+fn test_generated_jigsaw_has_unique_solution() {
+    let mut board_generator = BoardGenerator::new_with_layout(BoardDifficulty::Medium, BoardLayout::Jigsaw);
+    let board = board_generator.generate_sudoku();
+    let solver = Solver::new(board_generator.get_regions());
+
+    assert_eq!(solver.count_solutions(&board, 2), 1);
+    assert_eq!(solver.solve(&board), board_generator.get_solved_board());
+}
+
+#[test]
+fn test_generated_samurai_has_unique_solution() {
+    let mut board_generator = BoardGenerator::new_with_layout(BoardDifficulty::Beginner, BoardLayout::Samurai);
+    let board = board_generator.generate_sudoku();
+    let solver = Solver::new(board_generator.get_regions());
+
+    assert_eq!(board.len(), 21);
+    // The generator proved the board unique within this many steps
+    assert_eq!(solver.count_solutions_within(&board, 2, 10_000), Some(1));
+    // cells outside the five grids stay empty
+    assert_eq!(board[0][10], None);
+}
+
+#[test]
+fn test_complete_board_follows_the_rules() {
+    let solver = Solver::new(sudoku::regions::Regions::classic());
+    let mut board = solver.solve(&vec![vec![None; 9]; 9]).unwrap();
+    assert!(solver.is_complete(&board));
+
+    board[0].swap(0, 1);
+    assert!(!solver.is_complete(&board));
+    board[0][0] = None;
+    assert!(!solver.is_complete(&board));
+}
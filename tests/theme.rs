@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use sudoku::theme::{Color, Theme};
+
+#[test]
+fn test_parse_overrides_base_theme() {
+    let theme = Theme::parse("# comment\nname = Night\nbase = dark\nselection = #102030\npalette_2 = #405060\n").unwrap();
+
+    assert_eq!(theme.name, "Night");
+    assert_eq!(theme.selection, Color::rgb(0x10, 0x20, 0x30));
+    assert_eq!(theme.palette[1], Color::rgb(0x40, 0x50, 0x60));
+    assert_eq!(theme.palette[0], Theme::dark().palette[0]);
+    assert_eq!(theme.background, Theme::dark().background);
+}
+
+#[test]
+fn test_parse_rejects_bad_lines() {
+    assert!(Theme::parse("backgrund = #ffffff").unwrap_err().contains("unknown color"));
+    assert!(Theme::parse("background = white").unwrap_err().contains("line 1"));
+    assert!(Theme::parse("background").is_err());
+    assert!(Theme::parse("palette_6 = #ffffff").unwrap_err().contains("unknown color"));
+}
+
+#[test]
+fn test_bundled_theme_loads() {
+    let theme = Theme::from_file(Path::new("assets/themes/solarized.theme")).unwrap();
+
+    assert_eq!(theme.name, "Solarized");
+    assert_ne!(theme, Theme::light());
+}
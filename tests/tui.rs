@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use sudoku::game::GameState;
+use sudoku::input::KeyChord;
+use sudoku::puzzle_queue::PuzzleQueue;
+use sudoku::tui;
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+#[test]
+fn test_terminal_keys_become_chords() {
+    let chord = |code| tui::key_chord(key(code, KeyModifiers::NONE)).map(|chord| chord.to_string());
+    assert_eq!(chord(KeyCode::Char('5')), Some("5".to_string()));
+    // Shifted digits arrive as the symbol they type
+    assert_eq!(chord(KeyCode::Char('%')), Some("Shift+5".to_string()));
+    assert_eq!(chord(KeyCode::Char('K')), Some("Shift+K".to_string()));
+    assert_eq!(chord(KeyCode::Esc), Some("Escape".to_string()));
+    assert_eq!(
+        tui::key_chord(key(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+        Some(KeyChord { ctrl: true, ..KeyChord::parse("Z").unwrap() })
+    );
+}
+
+#[test]
+fn test_keys_play_the_game() {
+    let mut game_state = GameState::new();
+    assert!(tui::handle_key(&mut game_state, key(KeyCode::Char('n'), KeyModifiers::NONE)));
+    tui::advance(&mut game_state, Duration::ZERO);
+    assert!(game_state.board_initialized);
+    assert_eq!(game_state.selected_square, Some((0, 0)));
+
+    let (row, col) = (0..9)
+        .flat_map(|row| (0..9).map(move |col| (row, col)))
+        .find(|&(row, col)| game_state.board[row][col].is_none())
+        .unwrap();
+    game_state.select(row as i32, col as i32);
+    tui::handle_key(&mut game_state, key(KeyCode::Char('3'), KeyModifiers::ALT));
+    assert_eq!(game_state.center_marks[row][col], Some(vec![3]));
+    tui::handle_key(&mut game_state, key(KeyCode::Char('@'), KeyModifiers::NONE));
+    assert_eq!(game_state.corner_marks[row][col], Some(vec![2]));
+    tui::handle_key(&mut game_state, key(KeyCode::Char('7'), KeyModifiers::NONE));
+    assert_eq!(game_state.board[row][col], Some(7));
+
+    // Escape asks first, a second one quits
+    assert!(tui::handle_key(&mut game_state, key(KeyCode::Esc, KeyModifiers::NONE)));
+    assert!(game_state.quit_requested);
+    assert!(!tui::handle_key(&mut game_state, key(KeyCode::Esc, KeyModifiers::NONE)));
+}
+
+#[test]
+fn test_cursor_waits_for_the_queued_puzzle() {
+    let mut game_state = GameState::new();
+    game_state.puzzle_queue = Some(PuzzleQueue::start());
+    tui::handle_key(&mut game_state, key(KeyCode::Char('n'), KeyModifiers::NONE));
+
+    let deadline = Instant::now() + Duration::from_secs(120);
+    while !game_state.board_initialized && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        tui::advance(&mut game_state, Duration::from_millis(10));
+    }
+    assert!(game_state.board_initialized);
+    assert_eq!(game_state.selected_square, Some((0, 0)));
+}
+
+#[test]
+fn test_board_is_drawn_with_box_characters() {
+    let mut game_state = GameState::new();
+    game_state.new_puzzle();
+    let screen = tui::draw(&game_state, 80, 30);
+
+    assert_eq!(screen.line(2), "  ┏━━━┯━━━┯━━━┳━━━┯━━━┯━━━┳━━━┯━━━┯━━━┓");
+    assert_eq!(screen.line(4), "  ┠───┼───┼───╂───┼───┼───╂───┼───┼───┨");
+    assert_eq!(screen.line(8), "  ┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫");
+    assert_eq!(screen.line(20), "  ┗━━━┷━━━┷━━━┻━━━┷━━━┷━━━┻━━━┷━━━┷━━━┛");
+    let digit = game_state.board[0][0].map_or(' ', |digit| char::from_digit(digit as u32, 10).unwrap());
+    assert_eq!(screen.cell(4, 3).ch, digit);
+}
+
+#[test]
+fn test_small_terminal_asks_for_more_room() {
+    let game_state = GameState::new();
+    let screen = tui::draw(&game_state, 30, 10);
+    assert!(screen.line(0).starts_with("Make the terminal at least"));
+}
+
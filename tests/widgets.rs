@@ -0,0 +1,115 @@
+use sudoku::game::{GameState, GuiData};
+use sudoku::widgets::{button_at, layout_buttons, ButtonAction};
+
+#[test]
+fn test_buttons_are_hit_where_they_are_drawn() {
+    let game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    assert_eq!(buttons.len(), 12);
+    for button in &buttons {
+        let center = button.rect.center();
+        assert_eq!(button_at(&buttons, center.x(), center.y()).map(|b| b.action), Some(button.action));
+    }
+    assert!(button_at(&buttons, gui_data.board_x + 5, gui_data.board_y + 5).is_none());
+}
+
+#[test]
+fn test_open_variant_menu_adds_entries_on_top() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    game_state.variant_menu_open = true;
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    assert_eq!(buttons.len(), 15);
+    let entry = buttons.last().unwrap();
+    assert_eq!(entry.action, ButtonAction::ToggleNonConsecutive);
+    let center = entry.rect.center();
+    assert_eq!(button_at(&buttons, center.x(), center.y()).map(|b| b.action), Some(entry.action));
+}
+
+#[test]
+fn test_settings_screen_replaces_other_buttons() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    game_state.settings_open = true;
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    assert_eq!(buttons.len(), 8);
+    assert_eq!(buttons[1].label, "Theme: Light");
+    assert_eq!(buttons[3].label, "Motion: On");
+    assert!(buttons.iter().all(|button| gui_data.board_area().contains_rect(button.rect)));
+}
+
+#[test]
+fn test_completion_screen_offers_three_buttons() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    game_state.new_puzzle();
+    game_state.edit(|game_state| {
+        game_state.board = game_state.solved_board.clone();
+        game_state.board[0][0] = None;
+    });
+    game_state.select(0, 0);
+    game_state.hint();
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    let actions: Vec<ButtonAction> = buttons.iter().map(|button| button.action).collect();
+    assert_eq!(actions, [ButtonAction::CloseSummary, ButtonAction::NewPuzzle, ButtonAction::Share]);
+    assert!(buttons.iter().all(|button| button.rect.bottom() < gui_data.summary_area().top()));
+}
+
+#[test]
+fn test_color_mode_shows_the_palette() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    game_state.color_mode = true;
+    game_state.paint_color = 2;
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    assert_eq!(buttons.len(), 12);
+    let swatch = buttons.iter().find(|button| button.action == ButtonAction::PickColor(2)).unwrap();
+    assert!(swatch.active);
+    assert_eq!(buttons.last().unwrap().action, ButtonAction::ClearColors);
+}
+
+#[test]
+fn test_library_screen_lists_a_page_of_puzzles() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    game_state.library.puzzles = sudoku::library::Library::load_dir(std::path::Path::new("assets/puzzles"));
+    game_state.library_open = true;
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    // The filter, eight puzzles, and previous, next and close
+    assert_eq!(buttons.len(), 12);
+    assert_eq!(buttons[0].label, "Show: All  (page 1 of 4)");
+    assert_eq!(buttons[1].label, "beginner #1, Beginner, new");
+    assert_eq!(buttons[8].action, ButtonAction::OpenLibraryPuzzle(7));
+
+    game_state.library_filter = Some(sudoku::board_generator::BoardDifficulty::Hard);
+    let buttons = layout_buttons(&game_state, &gui_data);
+    assert_eq!(buttons.len(), 10);
+    assert_eq!(buttons[0].label, "Show: Hard  (page 1 of 1)");
+}
+
+#[test]
+fn test_calendar_shows_the_days_of_a_month() {
+    let mut game_state = GameState::new();
+    let gui_data = GuiData::new(800, 1000, 1.0);
+    let solved = chrono::NaiveDate::from_ymd_opt(2026, 2, 3).unwrap();
+    game_state.records.record_daily(solved, std::time::Duration::from_secs(200), solved);
+    game_state.calendar_month = chrono::NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+    game_state.calendar_open = true;
+    let buttons = layout_buttons(&game_state, &gui_data);
+
+    // The month with its arrows, 28 days, and today's puzzle and close
+    assert_eq!(buttons.len(), 33);
+    assert_eq!(buttons[1].label, "February 2026, streak 0 (best 1)");
+    assert_eq!(buttons[3].label, "1");
+    let day = buttons.iter().find(|button| button.label == "3").unwrap();
+    assert!(day.active);
+    assert_eq!(day.action, ButtonAction::OpenDaily(chrono::Datelike::num_days_from_ce(&solved)));
+    assert!(buttons.iter().all(|button| gui_data.board_area().contains_rect(button.rect)));
+}